@@ -191,9 +191,10 @@ class XDRData:
                     
         return timestamps, values
         
-    def get_parameter_statistics(self, dataref_index: int, array_index: int = 0) -> Dict:
+    def get_parameter_statistics(self, dataref_index: int, array_index: int = 0,
+                                 time_range: Optional[tuple] = None) -> Dict:
         """Calculate statistics for a parameter"""
-        timestamps, values = self.get_parameter_data(dataref_index, array_index)
+        timestamps, values = self.get_parameter_data(dataref_index, array_index, time_range)
         
         if not values:
             return {}
@@ -222,14 +223,18 @@ class XDRData:
                         'index': i,
                         'array_index': j,
                         'name': f"{dr['name']}[{j}]",
-                        'type': dr['type']
+                        'type': dr['type'],
+                        'array_size': dr['array_size'],
+                        'is_array_element': True
                     })
             else:
                 params.append({
                     'index': i,
                     'array_index': 0,
                     'name': dr['name'],
-                    'type': dr['type']
+                    'type': dr['type'],
+                    'array_size': 0,
+                    'is_array_element': False
                 })
         return params
     
@@ -371,6 +376,8 @@ def get_statistics():
     
     data = request.get_json()
     params = data.get('parameters', [])
+    time_range = data.get('time_range')
+    tr = (time_range[0], time_range[1]) if time_range and len(time_range) >= 2 else None
     
     result = []
     
@@ -378,7 +385,7 @@ def get_statistics():
         idx = param['index']
         arr_idx = param.get('array_index', 0)
         
-        stats = current_data.get_parameter_statistics(idx, arr_idx)
+        stats = current_data.get_parameter_statistics(idx, arr_idx, tr)
         stats['name'] = param['name']
         result.append(stats)
     
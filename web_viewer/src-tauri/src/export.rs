@@ -0,0 +1,584 @@
+use crate::xdr::{DataValue, Parameter, XDRData};
+use crate::Anomaly;
+use arrow_array::builder::{Float32Builder, Float64Builder, Int32Builder, StringBuilder};
+use arrow_array::{ArrayRef, Float32Array, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use serde_json::json;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A full-resolution position fix used by the track exporters
+struct TrackPoint {
+    timestamp: f32,
+    lat: f64,
+    lon: f64,
+    /// Altitude in meters MSL
+    alt: f64,
+}
+
+/// Escape text for use in XML content and attribute values
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Typed Arrow column for one exported parameter; missing values become nulls
+enum ColumnBuilder {
+    Float(Float32Builder),
+    Int(Int32Builder),
+    Double(Float64Builder),
+    Text(StringBuilder),
+}
+
+impl ColumnBuilder {
+    /// Builder matching a dataref's recorded type
+    fn for_type(data_type: &str, capacity: usize) -> Self {
+        match data_type {
+            "int" => ColumnBuilder::Int(Int32Builder::with_capacity(capacity)),
+            "double" => ColumnBuilder::Double(Float64Builder::with_capacity(capacity)),
+            "string" => ColumnBuilder::Text(StringBuilder::new()),
+            _ => ColumnBuilder::Float(Float32Builder::with_capacity(capacity)),
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnBuilder::Float(_) => DataType::Float32,
+            ColumnBuilder::Int(_) => DataType::Int32,
+            ColumnBuilder::Double(_) => DataType::Float64,
+            ColumnBuilder::Text(_) => DataType::Utf8,
+        }
+    }
+
+    /// Append one frame's value (or array element); a value of another type
+    /// or a missing one is appended as null
+    fn append(&mut self, value: Option<&DataValue>, array_index: usize) {
+        match self {
+            ColumnBuilder::Float(b) => b.append_option(match value {
+                Some(DataValue::Float(v)) => Some(*v),
+                Some(DataValue::FloatArray(arr)) => arr.get(array_index).copied(),
+                _ => None,
+            }),
+            ColumnBuilder::Int(b) => b.append_option(match value {
+                Some(DataValue::Int(v)) => Some(*v),
+                Some(DataValue::IntArray(arr)) => arr.get(array_index).copied(),
+                _ => None,
+            }),
+            ColumnBuilder::Double(b) => b.append_option(match value {
+                Some(DataValue::Double(v)) => Some(*v),
+                Some(DataValue::DoubleArray(arr)) => arr.get(array_index).copied(),
+                _ => None,
+            }),
+            ColumnBuilder::Text(b) => b.append_option(match value {
+                Some(DataValue::String(v)) => Some(v.as_str()),
+                Some(DataValue::StringArray(arr)) => arr.get(array_index).map(String::as_str),
+                _ => None,
+            }),
+        }
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Float(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Int(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Double(mut b) => Arc::new(b.finish()),
+            ColumnBuilder::Text(mut b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+impl XDRData {
+    /// Every valid position fix with altitude converted to meters.
+    /// Uses the same dataref discovery as `get_flight_path`.
+    fn track_points(&self) -> io::Result<Vec<TrackPoint>> {
+        let (lat_idx, lon_idx, alt_idx) = self.find_position_datarefs();
+        let (lat_i, lon_i, alt_i) = match (lat_idx, lon_idx, alt_idx) {
+            (Some(lat), Some(lon), Some(alt)) => (lat, lon, alt),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "Position data not found",
+                ))
+            }
+        };
+
+        let points = self
+            .frames
+            .iter()
+            .filter_map(|frame| {
+                let lat = Self::frame_value(frame, lat_i, 0)?;
+                let lon = Self::frame_value(frame, lon_i, 0)?;
+                let alt = self.altitude_m(frame, alt_i)?;
+                if lat == 0.0 && lon == 0.0 {
+                    return None;
+                }
+                Some(TrackPoint {
+                    timestamp: frame.timestamp,
+                    lat,
+                    lon,
+                    alt,
+                })
+            })
+            .collect();
+
+        Ok(points)
+    }
+
+    /// The recording's schema without any frame values: header fields, dataref
+    /// definitions, the derived plottable parameters and any schema warnings
+    pub fn schema_json(&self) -> serde_json::Value {
+        json!({
+            "header": self.header,
+            "datarefs": self.datarefs,
+            "parameters": self.get_all_plottable_parameters(),
+            "schema_warnings": self.schema_warnings,
+        })
+    }
+
+    /// Write the flight path as a GeoJSON `Feature` with a `LineString` geometry.
+    /// Coordinates are `[longitude, latitude, altitude_m]` per the GeoJSON spec.
+    pub fn export_geojson(&self, writer: &mut impl Write) -> io::Result<()> {
+        let coordinates: Vec<[f64; 3]> = self
+            .track_points()?
+            .iter()
+            .map(|p| [p.lon, p.lat, p.alt])
+            .collect();
+
+        let feature = json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": coordinates,
+            },
+            "properties": {
+                "start_datetime": self.header.start_datetime,
+                "end_datetime": self.header.end_datetime,
+                "departure": self.header.departure_airport.as_ref().map(|a| &a.icao),
+                "arrival": self.header.arrival_airport.as_ref().map(|a| &a.icao),
+            },
+        });
+
+        serde_json::to_writer(&mut *writer, &feature)?;
+        writer.flush()
+    }
+
+    /// Write the flight path as a GPX 1.1 track with one segment.
+    /// Point times are absolute, derived from the header start time.
+    pub fn export_gpx(&self, writer: &mut impl Write) -> io::Result<()> {
+        let points = self.track_points()?;
+
+        let name = Path::new(&self.filepath)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "XBlackBox flight".to_string());
+
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<gpx version="1.1" creator="XBlackBox Viewer" xmlns="http://www.topografix.com/GPX/1/1">"#
+        )?;
+        writeln!(writer, "  <trk>")?;
+        writeln!(writer, "    <name>{}</name>", xml_escape(&name))?;
+        writeln!(writer, "    <trkseg>")?;
+
+        for point in &points {
+            write!(writer, r#"      <trkpt lat="{}" lon="{}"><ele>{}</ele>"#, point.lat, point.lon, point.alt)?;
+            if let Some(time) = self.datetime_at(point.timestamp) {
+                write!(writer, "<time>{}</time>", time.format("%Y-%m-%dT%H:%M:%S%.3fZ"))?;
+            }
+            writeln!(writer, "</trkpt>")?;
+        }
+
+        writeln!(writer, "    </trkseg>")?;
+        writeln!(writer, "  </trk>")?;
+        writeln!(writer, "</gpx>")?;
+
+        writer.flush()
+    }
+
+    /// Write the selected parameters as a Parquet file: a `timestamp` column
+    /// followed by one column per parameter, typed after its dataref (float,
+    /// int, double or string). Values missing from a frame are null.
+    pub fn export_parquet(&self, params: &[Parameter], path: &Path) -> io::Result<()> {
+        let rows = self.frames.len();
+        let mut builders: Vec<ColumnBuilder> = params
+            .iter()
+            .map(|param| {
+                let data_type = self.datarefs.get(param.index).map_or("float", |dr| dr.data_type.as_str());
+                ColumnBuilder::for_type(data_type, rows)
+            })
+            .collect();
+
+        for frame in &self.frames {
+            for (param, builder) in params.iter().zip(builders.iter_mut()) {
+                builder.append(frame.values.get(param.index), param.array_index);
+            }
+        }
+
+        let mut fields = vec![Field::new("timestamp", DataType::Float32, false)];
+        let mut columns: Vec<ArrayRef> = vec![Arc::new(Float32Array::from_iter_values(
+            self.frames.iter().map(|f| f.timestamp),
+        ))];
+        for (param, builder) in params.iter().zip(builders) {
+            let name = self.parameter_name(param.index, param.array_index);
+            fields.push(Field::new(name, builder.data_type(), true));
+            columns.push(builder.finish());
+        }
+
+        let schema = Arc::new(Schema::new(fields));
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)?;
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None).map_err(io::Error::other)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+        writer.close().map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    /// Write the selected parameters as CSV: a `timestamp` column followed by
+    /// one column per parameter. Values missing from a frame are left empty.
+    pub fn export_csv(&self, params: &[Parameter], writer: &mut impl Write) -> io::Result<()> {
+        let mut header = vec![Cow::Borrowed("timestamp")];
+        for param in params {
+            let name = self.parameter_name(param.index, param.array_index);
+            header.push(Cow::Owned(csv_field(&name).into_owned()));
+        }
+        writeln!(writer, "{}", header.join(","))?;
+
+        for frame in &self.frames {
+            let mut row = vec![frame.timestamp.to_string()];
+
+            for param in params {
+                let cell = match frame.values.get(param.index) {
+                    Some(DataValue::Float(v)) => v.to_string(),
+                    Some(DataValue::Int(v)) => v.to_string(),
+                    Some(DataValue::String(v)) => csv_field(v).into_owned(),
+                    Some(DataValue::FloatArray(arr)) => arr
+                        .get(param.array_index)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    Some(DataValue::IntArray(arr)) => arr
+                        .get(param.array_index)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    Some(DataValue::Double(v)) => v.to_string(),
+                    Some(DataValue::DoubleArray(arr)) => arr
+                        .get(param.array_index)
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    Some(DataValue::StringArray(arr)) => arr
+                        .get(param.array_index)
+                        .map(|v| csv_field(v).into_owned())
+                        .unwrap_or_default(),
+                    None => String::new(),
+                };
+                row.push(cell);
+            }
+
+            writeln!(writer, "{}", row.join(","))?;
+        }
+
+        writer.flush()
+    }
+
+    /// Write anomalies as CSV, one row each, for aggregation across flights.
+    /// `timestamp` is relative seconds; `datetime` is absolute UTC from the header.
+    /// Each row is a coalesced event ending at `end_timestamp`.
+    pub(crate) fn export_anomalies_csv(
+        &self,
+        anomalies: &[Anomaly],
+        writer: &mut impl Write,
+    ) -> io::Result<()> {
+        writeln!(writer, "timestamp,datetime,severity,parameter,description,value,end_timestamp,peak_value")?;
+
+        for anomaly in anomalies {
+            let datetime = self
+                .datetime_at(anomaly.timestamp)
+                .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{}",
+                anomaly.timestamp,
+                datetime,
+                csv_field(&anomaly.severity),
+                csv_field(&anomaly.parameter),
+                csv_field(&anomaly.description),
+                anomaly.value,
+                anomaly.end_timestamp,
+                anomaly.peak_value
+            )?;
+        }
+
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xdr::{DataFrame, DatarefDef};
+
+    /// Data with latitude, longitude and elevation (meters) datarefs
+    fn position_data(points: &[(f32, f32, f32, f32)]) -> XDRData {
+        let mut data = XDRData::new();
+        for name in ["latitude", "longitude", "elevation"] {
+            data.datarefs.push(DatarefDef {
+                name: format!("sim/flightmodel/position/{}", name),
+                data_type: "float".to_string(),
+                array_size: 0,
+            });
+        }
+        for &(timestamp, lat, lon, alt) in points {
+            data.frames.push(DataFrame {
+                timestamp,
+                values: vec![DataValue::Float(lat), DataValue::Float(lon), DataValue::Float(alt)],
+            });
+        }
+        data
+    }
+
+    #[test]
+    fn test_export_geojson() {
+        let data = position_data(&[(0.0, 0.0, 0.0, 0.0), (1.0, 47.5, 8.5, 400.0), (2.0, 47.6, 8.6, 500.0)]);
+
+        let mut output = Vec::new();
+        data.export_geojson(&mut output).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(value["type"], "Feature");
+        assert_eq!(value["geometry"]["type"], "LineString");
+        let coords = value["geometry"]["coordinates"].as_array().unwrap();
+        // The uninitialized 0/0 fix is skipped; longitude comes first
+        assert_eq!(coords.len(), 2);
+        assert_eq!(coords[0][0], 8.5);
+        assert_eq!(coords[0][1], 47.5);
+        assert_eq!(coords[0][2], 400.0);
+    }
+
+    #[test]
+    fn test_schema_json() {
+        let mut data = position_data(&[(0.0, 47.5, 8.5, 400.0), (1.0, 47.6, 8.6, 500.0)]);
+        data.header.version = 2;
+        data.datarefs.push(DatarefDef {
+            name: "sim/test/egt".to_string(),
+            data_type: "float".to_string(),
+            array_size: 2,
+        });
+
+        let schema = data.schema_json();
+        assert_eq!(schema["header"]["version"], 2);
+        let datarefs = schema["datarefs"].as_array().unwrap();
+        assert_eq!(datarefs.len(), 4);
+        assert_eq!(datarefs[3]["type"], "float");
+        assert_eq!(datarefs[3]["array_size"], 2);
+        let parameters = schema["parameters"].as_array().unwrap();
+        assert_eq!(parameters.len(), 5);
+        assert_eq!(parameters[4]["is_array_element"], true);
+        assert!(schema.get("frames").is_none());
+    }
+
+    #[test]
+    fn test_export_gpx() {
+        let mut data = position_data(&[(0.0, 47.5, 8.5, 400.0), (1.5, 47.6, 8.6, 500.0)]);
+        data.filepath = "/tmp/a&b.xdr".to_string();
+        data.header.start_timestamp = 1_700_000_000;
+
+        let mut output = Vec::new();
+        data.export_gpx(&mut output).unwrap();
+        let gpx = String::from_utf8(output).unwrap();
+
+        // Check the document against the parts of the GPX 1.1 schema we emit
+        let doc = roxmltree::Document::parse(&gpx).unwrap();
+        let root = doc.root_element();
+        assert_eq!(root.tag_name().name(), "gpx");
+        assert_eq!(root.tag_name().namespace(), Some("http://www.topografix.com/GPX/1/1"));
+        assert_eq!(root.attribute("version"), Some("1.1"));
+        assert!(root.attribute("creator").is_some());
+
+        let trk = root.children().find(|n| n.has_tag_name("trk")).unwrap();
+        assert_eq!(trk.children().find(|n| n.has_tag_name("name")).unwrap().text(), Some("a&b.xdr"));
+        let segments: Vec<_> = trk.children().filter(|n| n.has_tag_name("trkseg")).collect();
+        assert_eq!(segments.len(), 1);
+
+        let points: Vec<_> = segments[0].children().filter(|n| n.is_element()).collect();
+        assert_eq!(points.len(), 2);
+        for point in &points {
+            assert!(point.has_tag_name("trkpt"));
+            let lat: f64 = point.attribute("lat").unwrap().parse().unwrap();
+            let lon: f64 = point.attribute("lon").unwrap().parse().unwrap();
+            assert!((-90.0..=90.0).contains(&lat));
+            assert!((-180.0..=180.0).contains(&lon));
+
+            // <ele> must precede <time>
+            let children: Vec<_> = point.children().filter(|n| n.is_element()).collect();
+            assert_eq!(children[0].tag_name().name(), "ele");
+            assert_eq!(children[1].tag_name().name(), "time");
+        }
+
+        let time = points[1].children().find(|n| n.has_tag_name("time")).unwrap();
+        assert_eq!(time.text(), Some("2023-11-14T22:13:21.500Z"));
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let mut data = XDRData::new();
+        data.datarefs.push(DatarefDef {
+            name: "sim/test/array".to_string(),
+            data_type: "float".to_string(),
+            array_size: 2,
+        });
+        data.datarefs.push(DatarefDef {
+            name: "sim/test/label".to_string(),
+            data_type: "string".to_string(),
+            array_size: 0,
+        });
+        data.frames.push(DataFrame {
+            timestamp: 0.5,
+            values: vec![
+                DataValue::FloatArray(vec![1.5, 2.5]),
+                DataValue::String("a,b".to_string()),
+            ],
+        });
+        // Short frame: the string column is missing
+        data.frames.push(DataFrame {
+            timestamp: 1.0,
+            values: vec![DataValue::FloatArray(vec![3.0, 4.0])],
+        });
+
+        let params = vec![
+            Parameter {
+                index: 0,
+                array_index: 1,
+                name: String::new(),
+                display_name: String::new(),
+                data_type: "float".to_string(),
+                array_size: 2,
+                is_array_element: true,
+            },
+            Parameter {
+                index: 1,
+                array_index: 0,
+                name: String::new(),
+                display_name: String::new(),
+                data_type: "string".to_string(),
+                array_size: 0,
+                is_array_element: false,
+            },
+        ];
+
+        let mut output = Vec::new();
+        data.export_csv(&params, &mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "timestamp,sim/test/array[1],sim/test/label");
+        assert_eq!(lines[1], "0.5,2.5,\"a,b\"");
+        assert_eq!(lines[2], "1,4,");
+    }
+
+    #[test]
+    fn test_export_parquet() {
+        use arrow_array::{Array, Float64Array, Int32Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let mut data = XDRData::new();
+        for (name, data_type, array_size) in [
+            ("sim/test/gear", "int", 2),
+            ("sim/test/altitude", "double", 0),
+            ("sim/test/label", "string", 0),
+        ] {
+            data.datarefs.push(DatarefDef {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+                array_size,
+            });
+        }
+        data.frames.push(DataFrame {
+            timestamp: 0.0,
+            values: vec![
+                DataValue::IntArray(vec![0, 1]),
+                DataValue::Double(1500.25),
+                DataValue::String("taxi".to_string()),
+            ],
+        });
+        // Short frame: the string column is missing
+        data.frames.push(DataFrame {
+            timestamp: 0.5,
+            values: vec![DataValue::IntArray(vec![1, 1]), DataValue::Double(1501.5)],
+        });
+
+        let param = |index: usize, array_index: usize| Parameter {
+            index,
+            array_index,
+            name: String::new(),
+            display_name: String::new(),
+            data_type: String::new(),
+            array_size: 0,
+            is_array_element: false,
+        };
+        let path = std::env::temp_dir().join("xdr_test_export.parquet");
+        data.export_parquet(&[param(0, 1), param(1, 0), param(2, 0)], &path).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        let batch = &batches[0];
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, vec!["timestamp", "sim/test/gear[1]", "sim/test/altitude", "sim/test/label"]);
+        assert_eq!(batch.num_rows(), 2);
+
+        let gear = batch.column(1).as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(gear.values().to_vec(), vec![1, 1]);
+        let altitude = batch.column(2).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(altitude.value(0), 1500.25);
+        let label = batch.column(3).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(label.value(0), "taxi");
+        assert!(label.is_null(1));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_export_anomalies_csv() {
+        let mut data = XDRData::new();
+        data.header.start_timestamp = 1_700_000_000;
+        let anomaly = |timestamp: f32, description: &str| Anomaly {
+            timestamp,
+            end_timestamp: timestamp + 2.0,
+            frame_index: 0,
+            severity: "high".to_string(),
+            description: description.to_string(),
+            parameter: "G-Force".to_string(),
+            value: 2.5,
+            peak_value: 3.25,
+        };
+        let anomalies = vec![anomaly(1.5, "Excessive G-force"), anomaly(10.0, "Load \"high\", sustained")];
+
+        let mut output = Vec::new();
+        data.export_anomalies_csv(&anomalies, &mut output).unwrap();
+        let csv = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "timestamp,datetime,severity,parameter,description,value,end_timestamp,peak_value");
+        assert_eq!(lines[1], "1.5,2023-11-14T22:13:21.500Z,high,G-Force,Excessive G-force,2.5,3.5,3.25");
+        assert_eq!(lines[2], "10,2023-11-14T22:13:30.000Z,high,G-Force,\"Load \"\"high\"\", sustained\",2.5,12,3.25");
+    }
+}
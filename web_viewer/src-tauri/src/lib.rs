@@ -1,60 +1,302 @@
 mod xdr;
+mod export;
 mod logger;
 mod security;
+mod report;
+mod units;
+mod writer;
 
-use logger::AppLogger;
-use security::{validate_file_path, sanitize_error_message};
+use logger::{AppLogger, LogFormat, DEFAULT_RECENT_LINES};
+use report::ReportFormat;
+use security::{
+    validate_file_path_with_policy, validate_output_path, sanitize_error_message, PathPolicy, SecurityError,
+    DEFAULT_MAX_FILE_SIZE,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
+
+/// Event carrying `LoadProgress` while `load_file` parses a recording
+const LOAD_PROGRESS_EVENT: &str = "load-progress";
+
+/// Most recordings kept in memory at once; loading more evicts the oldest,
+/// whose id is reported back in `LoadFileResponse::evicted_file_id`
+const MAX_LOADED_FILES: usize = 4;
+
+/// Recordings loaded in this session, keyed by the id `load_file` returns.
+/// Single-file commands operate on the current (most recently loaded) one.
+#[derive(Default)]
+struct LoadedFiles {
+    files: HashMap<String, xdr::XDRData>,
+    /// Ids in load order, oldest first
+    order: Vec<String>,
+    current: Option<String>,
+    next_id: u64,
+}
+
+impl LoadedFiles {
+    /// Store a recording and make it current. Returns its id and, when the
+    /// cap was reached, the id of the oldest recording evicted to make room.
+    fn insert(&mut self, data: xdr::XDRData) -> (String, Option<String>) {
+        let evicted = if self.order.len() >= MAX_LOADED_FILES {
+            let oldest = self.order.remove(0);
+            self.files.remove(&oldest);
+            Some(oldest)
+        } else {
+            None
+        };
+
+        self.next_id += 1;
+        let id = format!("file-{}", self.next_id);
+        self.files.insert(id.clone(), data);
+        self.order.push(id.clone());
+        self.current = Some(id.clone());
+        (id, evicted)
+    }
+
+    /// Make a loaded recording current; false if the id is unknown
+    fn select(&mut self, id: &str) -> bool {
+        if !self.files.contains_key(id) {
+            return false;
+        }
+        self.current = Some(id.to_string());
+        true
+    }
+
+    fn get(&self, id: &str) -> Option<&xdr::XDRData> {
+        self.files.get(id)
+    }
+
+    /// The most recently loaded (or selected) recording
+    fn current(&self) -> Option<&xdr::XDRData> {
+        self.current.as_deref().and_then(|id| self.files.get(id))
+    }
+
+    fn current_mut(&mut self) -> Option<&mut xdr::XDRData> {
+        self.current.as_deref().and_then(|id| self.files.get_mut(id))
+    }
+
+    /// Remove a recording; the newest remaining one becomes current
+    fn remove(&mut self, id: &str) -> Option<xdr::XDRData> {
+        let data = self.files.remove(id)?;
+        self.order.retain(|loaded| loaded != id);
+        if self.current.as_deref() == Some(id) {
+            self.current = self.order.last().cloned();
+        }
+        Some(data)
+    }
+}
 
 // Global state for XDR data and logger
 struct AppState {
-    xdr_data: Mutex<Option<xdr::XDRData>>,
+    files: Mutex<LoadedFiles>,
     logger: AppLogger,
+    max_file_size: u64,
+    /// Display name overrides applied to every loaded file
+    display_name_overrides: Mutex<HashMap<String, String>>,
+    /// Invalid-value filter applied to every loaded file
+    nan_policy: Mutex<Option<xdr::NanPolicy>>,
+    /// Whether paths that are themselves symlinks may be opened
+    follow_symlinks: bool,
+    /// Directories opened and exported files must lie within (empty means anywhere)
+    allowed_dirs: Vec<PathBuf>,
+}
+
+impl AppState {
+    /// Validate a user-supplied path with the configured symlink policy
+    fn validate_path(&self, filepath: &str, max_bytes: u64) -> Result<PathBuf, SecurityError> {
+        let policy = PathPolicy {
+            allowed_dirs: self.allowed_dirs.clone(),
+            max_bytes,
+            follow_symlinks: self.follow_symlinks,
+        };
+        validate_file_path_with_policy(filepath, &policy)
+    }
+
+    /// Validate a user-supplied export destination ending in one of `extensions`
+    fn validate_output(&self, filepath: &str, extensions: &[&str]) -> Result<PathBuf, SecurityError> {
+        let policy = PathPolicy {
+            allowed_dirs: self.allowed_dirs.clone(),
+            follow_symlinks: self.follow_symlinks,
+            ..PathPolicy::default()
+        };
+        validate_output_path(filepath, extensions, &policy)
+    }
 }
 
+/// Environment variable overriding the maximum loadable file size in bytes
+const MAX_FILE_SIZE_ENV: &str = "XBLACKBOX_MAX_FILE_SIZE";
+
+/// Environment variable selecting the log file format (`text` or `json`)
+const LOG_FORMAT_ENV: &str = "XBLACKBOX_LOG_FORMAT";
+
+/// Environment variable that, when `0` or `false`, refuses to open symlinked paths
+const FOLLOW_SYMLINKS_ENV: &str = "XBLACKBOX_FOLLOW_SYMLINKS";
+
+/// Environment variable listing the only directories files may be opened from
+/// or exported to, separated like `PATH`
+const ALLOWED_DIRS_ENV: &str = "XBLACKBOX_ALLOWED_DIRS";
+
 // Request/Response types
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
+struct LoadProgress {
+    bytes_read: u64,
+    total_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
 struct LoadFileResponse {
     success: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    /// Id for addressing this recording in multi-file commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file_id: Option<String>,
+    /// Oldest recording unloaded to stay within `MAX_LOADED_FILES`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    evicted_file_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     header: Option<xdr::XDRHeader>,
     #[serde(skip_serializing_if = "Option::is_none")]
     parameters: Option<Vec<xdr::Parameter>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     frame_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    truncated: Option<bool>,
+    /// Set when frame timestamps are duplicated or go backwards
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invalid_timestamps: Option<bool>,
+    /// Frames holding fewer values than there are datarefs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    short_frame_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_rate: Option<SampleRateInfo>,
+    /// Damage found in the dataref definitions (e.g. names that weren't valid UTF-8)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    schema_warnings: Vec<String>,
+    /// Result of the frame checksum; absent when the file has none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    integrity_ok: Option<bool>,
+}
+
+/// Achieved logging rate compared with the header's nominal interval
+#[derive(Debug, Serialize)]
+struct SampleRateInfo {
+    /// Frames per second actually recorded
+    effective_rate: f64,
+    /// Frames per second implied by the header interval (0.0 if unset)
+    nominal_rate: f64,
+    min_interval: f32,
+    max_interval: f32,
+    mean_interval: f32,
+}
+
+impl LoadFileResponse {
+    fn failure(error: String) -> Self {
+        LoadFileResponse {
+            success: false,
+            error: Some(error),
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct GetDataRequest {
-    parameters: Vec<xdr::Parameter>,
+    /// Recorded parameters, optionally with a transform applied
+    parameters: Vec<xdr::DerivedParameter>,
     #[serde(default = "default_downsample")]
     downsample: usize,
+    #[serde(default)]
+    downsample_mode: xdr::DownsampleMode,
     time_range: Option<Vec<f32>>,
+    /// Convert recognized datarefs to this unit system for display
+    #[serde(default)]
+    unit_system: Option<units::UnitSystem>,
+    /// Also return absolute ISO-8601 UTC times alongside the relative seconds
+    #[serde(default)]
+    absolute_time: bool,
+    /// Return z-scores (value - mean) / std over the time range, for
+    /// overlaying parameters of different scales; units are not converted
+    #[serde(default)]
+    normalize: bool,
 }
 
 fn default_downsample() -> usize {
     1
 }
 
+#[derive(Debug, Deserialize)]
+struct GetDataByNameRequest {
+    names: Vec<String>,
+    #[serde(default = "default_downsample")]
+    downsample: usize,
+    time_range: Option<Vec<f32>>,
+}
+
 #[derive(Debug, Serialize)]
 struct ParameterData {
     timestamps: Vec<f32>,
     values: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unit: Option<&'static str>,
+    /// ISO-8601 UTC time of each sample, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datetimes: Option<Vec<String>>,
 }
 
 #[derive(Debug, Deserialize)]
 struct GetStatisticsRequest {
-    parameters: Vec<xdr::Parameter>,
+    parameters: Vec<xdr::DerivedParameter>,
+    /// `[start, end]` in seconds; statistics cover the whole recording when absent
+    time_range: Option<Vec<f32>>,
+}
+
+/// Series derived from a recorded parameter, labelled for the chart axis
+#[derive(Debug, Serialize)]
+struct DerivedParameterData {
+    name: String,
+    unit: String,
+    timestamps: Vec<f32>,
+    values: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetSmoothedRequest {
+    parameter: xdr::Parameter,
+    window: usize,
+    order: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchFramesRequest {
+    conditions: Vec<xdr::FrameCondition>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetCrossingsRequest {
+    parameter: xdr::Parameter,
+    level: f64,
+    direction: xdr::CrossDirection,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetThresholdIntegralRequest {
+    parameter: xdr::Parameter,
+    threshold: f64,
+    direction: xdr::ThresholdDirection,
 }
 
 #[derive(Debug, Deserialize)]
 struct GetCorrelationRequest {
-    parameters: Vec<xdr::Parameter>,
+    parameters: Vec<xdr::DerivedParameter>,
+    /// Resample every series onto a uniform grid with this interval (seconds)
+    resample_interval: Option<f32>,
 }
 
 #[derive(Debug, Serialize)]
@@ -63,33 +305,83 @@ struct CorrelationResponse {
     names: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct CorrelateAgainstRequest {
+    reference: xdr::Parameter,
+    others: Vec<xdr::Parameter>,
+}
+
+#[derive(Debug, Serialize)]
+struct ParameterCorrelation {
+    name: String,
+    correlation: f64,
+}
+
 #[derive(Debug, Serialize)]
 struct FlightPathResponse {
     latitudes: Vec<f64>,
     longitudes: Vec<f64>,
     altitudes: Vec<f64>,
     timestamps: Vec<f32>,
+    /// Bounding box of the returned points, for fitting the map
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bounds: Option<xdr::GeoBounds>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    center: Option<xdr::GeoPoint>,
+}
+
+impl FlightPathResponse {
+    fn new(latitudes: Vec<f64>, longitudes: Vec<f64>, altitudes: Vec<f64>, timestamps: Vec<f32>) -> Self {
+        let bounds = xdr::GeoBounds::from_points(&latitudes, &longitudes);
+        let center = bounds.as_ref().map(xdr::GeoBounds::center);
+        FlightPathResponse {
+            latitudes,
+            longitudes,
+            altitudes,
+            timestamps,
+            bounds,
+            center,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SpeedComparisonResponse {
+    timestamps: Vec<f32>,
+    derived_groundspeed: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recorded_groundspeed: Option<Vec<Option<f64>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    true_airspeed: Option<Vec<Option<f64>>>,
 }
 
 #[derive(Debug, Deserialize)]
 struct GetTableDataRequest {
     start: usize,
     count: usize,
+    /// Also return each row's absolute ISO-8601 UTC time
+    #[serde(default)]
+    absolute_time: bool,
 }
 
 #[derive(Debug, Serialize)]
 struct TableRow {
     index: usize,
     timestamp: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    datetime: Option<String>,
     values: Vec<DataValueJson>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 #[serde(untagged)]
 enum DataValueJson {
     Float(f32),
     Int(i32),
     String(String),
+    Double(f64),
+    /// Not recorded in this frame; serialized as `null`
+    Missing,
 }
 
 #[derive(Debug, Serialize)]
@@ -99,25 +391,157 @@ struct TableDataResponse {
     total: usize,
 }
 
+/// Default multiple of the nominal frame interval treated as a gap
+const DEFAULT_GAP_TOLERANCE: f32 = 3.0;
+
+#[derive(Debug, Serialize)]
+struct TimeGap {
+    start: f32,
+    end: f32,
+    duration: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct FrameAtTimeResponse {
+    headers: Vec<String>,
+    row: TableRow,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetTableDeltasRequest {
+    start: usize,
+    count: usize,
+    #[serde(default = "default_float_tolerance")]
+    float_tolerance: f32,
+}
+
+fn default_float_tolerance() -> f32 {
+    1e-6
+}
+
+#[derive(Debug, Serialize)]
+struct TableDeltaRow {
+    index: usize,
+    timestamp: f32,
+    columns: Vec<usize>,
+    values: Vec<DataValueJson>,
+}
+
+#[derive(Debug, Serialize)]
+struct TableDeltasResponse {
+    headers: Vec<String>,
+    rows: Vec<TableDeltaRow>,
+    total: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct PeekFileResponse {
+    header: xdr::XDRHeader,
+    datarefs: Vec<String>,
+    file_size: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct UnloadFileResponse {
+    success: bool,
+}
+
+/// Apply the session's display names and NaN policy to a freshly read
+/// recording, make it the current file and describe it for the frontend
+fn register_loaded_file(mut data: xdr::XDRData, state: &AppState) -> LoadFileResponse {
+    data.display_name_overrides = state.display_name_overrides.lock().unwrap().clone();
+    data.nan_policy = state.nan_policy.lock().unwrap().clone();
+    let header = data.header.clone();
+    let parameters = data.get_all_plottable_parameters();
+    let frame_count = data.frames.len();
+    let truncated = !data.is_complete();
+
+    state.logger.log_info(&format!(
+        "Successfully loaded file: {} frames, {} parameters",
+        frame_count,
+        parameters.len()
+    ));
+    if truncated {
+        state.logger.log_warning("Recording has no ENDR footer and may be truncated");
+    }
+    let timestamp_report = data.validate_timestamps();
+    if !timestamp_report.is_valid() {
+        state.logger.log_warning(&format!(
+            "Recording has {} duplicate and {} backwards timestamps",
+            timestamp_report.duplicate_count, timestamp_report.non_monotonic_count
+        ));
+    }
+
+    let short_frame_count = data.short_frame_count();
+    if short_frame_count > 0 {
+        state.logger.log_warning(&format!(
+            "{} frames have fewer values than datarefs; missing values are shown as empty",
+            short_frame_count
+        ));
+    }
+    for warning in &data.schema_warnings {
+        state.logger.log_warning(warning);
+    }
+    let schema_warnings = data.schema_warnings.clone();
+    let integrity_ok = data.integrity_ok();
+    if integrity_ok == Some(false) {
+        state
+            .logger
+            .log_warning("Frame checksum mismatch; the recording may be corrupted");
+    }
+
+    let nominal_rate = if header.interval > 0.0 { 1.0 / header.interval as f64 } else { 0.0 };
+    let sample_rate = SampleRateInfo {
+        effective_rate: data.effective_sample_rate(),
+        nominal_rate,
+        min_interval: timestamp_report.min_interval,
+        max_interval: timestamp_report.max_interval,
+        mean_interval: timestamp_report.mean_interval,
+    };
+
+    let (file_id, evicted_file_id) = state.files.lock().unwrap().insert(data);
+    if let Some(evicted) = &evicted_file_id {
+        state.logger.log_info(&format!(
+            "Unloaded {} to stay within {} loaded files",
+            evicted, MAX_LOADED_FILES
+        ));
+    }
+
+    LoadFileResponse {
+        success: true,
+        error: None,
+        file_id: Some(file_id),
+        evicted_file_id,
+        header: Some(header),
+        parameters: Some(parameters),
+        frame_count: Some(frame_count),
+        truncated: Some(truncated),
+        invalid_timestamps: Some(!timestamp_report.is_valid()),
+        short_frame_count: Some(short_frame_count),
+        sample_rate: Some(sample_rate),
+        schema_warnings,
+        integrity_ok,
+    }
+}
+
 // Tauri Commands
 #[tauri::command]
-async fn load_file(filepath: String, state: State<'_, AppState>) -> Result<LoadFileResponse, String> {
+async fn load_file(
+    filepath: String,
+    force: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<LoadFileResponse, String> {
     // Log the file load attempt
     state.logger.log_info(&format!("Attempting to load file: {}", sanitize_error_message(&filepath)));
     
     // Validate and sanitize the file path
-    let validated_path = match validate_file_path(&filepath) {
+    let validated_path = match state.validate_path(&filepath, state.max_file_size) {
         Ok(path) => path,
         Err(e) => {
             let error_msg = format!("File validation failed: {}", e);
             state.logger.log_error(&error_msg);
-            return Ok(LoadFileResponse {
-                success: false,
-                error: Some(sanitize_error_message(&error_msg)),
-                header: None,
-                parameters: None,
-                frame_count: None,
-            });
+            return Ok(LoadFileResponse::failure(sanitize_error_message(&error_msg)));
         }
     };
     
@@ -125,556 +549,3040 @@ async fn load_file(filepath: String, state: State<'_, AppState>) -> Result<LoadF
     state.logger.log_debug(&format!("Validated path: {}", validated_path.display()));
     
     // Attempt to read the XDR file
-    match xdr::XDRData::read(&validated_path) {
-        Ok(data) => {
-            let header = data.header.clone();
-            let parameters = data.get_all_plottable_parameters();
-            let frame_count = data.frames.len();
-
-            state.logger.log_info(&format!(
-                "Successfully loaded file: {} frames, {} parameters",
-                frame_count,
-                parameters.len()
-            ));
-
-            *state.xdr_data.lock().unwrap() = Some(data);
+    let read_result = if force.unwrap_or(false) {
+        state.logger.log_warning("Forcing load without format version check");
+        xdr::XDRData::read_with_force(&validated_path, true, state.max_file_size)
+    } else {
+        xdr::XDRData::read_with_progress(&validated_path, state.max_file_size, |bytes_read, total_bytes| {
+            // Progress is best effort; a closed window must not fail the load
+            let _ = app.emit(LOAD_PROGRESS_EVENT, LoadProgress { bytes_read, total_bytes });
+        })
+    };
 
-            Ok(LoadFileResponse {
-                success: true,
-                error: None,
-                header: Some(header),
-                parameters: Some(parameters),
-                frame_count: Some(frame_count),
-            })
-        }
+    match read_result {
+        Ok(data) => Ok(register_loaded_file(data, &state)),
         Err(e) => {
             let error_msg = format!("Failed to read XDR file: {}", e);
             state.logger.log_error(&error_msg);
             
-            Ok(LoadFileResponse {
-                success: false,
-                error: Some(sanitize_error_message(&e.to_string())),
-                header: None,
-                parameters: None,
-                frame_count: None,
-            })
+            Ok(LoadFileResponse::failure(sanitize_error_message(&e.to_string())))
         }
     }
 }
 
+/// Outcome of checking a recording on disk without loading it into the viewer
+#[derive(Debug, Serialize)]
+struct VerifyFileResponse {
+    /// Whether the file ends with an `ENDR` footer
+    complete: bool,
+    /// Frame checksum result; `None` when the file carries no checksum
+    integrity_ok: Option<bool>,
+    frame_count: usize,
+}
+
 #[tauri::command]
-async fn get_data(
-    request: GetDataRequest,
-    state: State<'_, AppState>,
-) -> Result<HashMap<String, ParameterData>, String> {
-    state.logger.log_debug(&format!("get_data called with {} parameters", request.parameters.len()));
-    
-    let data_guard = state.xdr_data.lock().unwrap();
-    let data = data_guard
-        .as_ref()
-        .ok_or_else(|| {
-            state.logger.log_warning("get_data called but no file loaded");
-            "No file loaded".to_string()
-        })?;
+async fn verify_file(filepath: String, state: State<'_, AppState>) -> Result<VerifyFileResponse, String> {
+    let validated_path = state.validate_path(&filepath, state.max_file_size).map_err(|e| {
+        let error_msg = format!("File validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
 
-    let time_range = request.time_range.as_ref().and_then(|tr| {
-        if tr.len() >= 2 {
-            Some((tr[0], tr[1]))
-        } else {
-            None
-        }
-    });
+    let data = xdr::XDRData::read(&validated_path, state.max_file_size).map_err(|e| {
+        state.logger.log_error(&format!("Failed to verify XDR file: {}", e));
+        sanitize_error_message(&e.to_string())
+    })?;
 
-    let mut result = HashMap::new();
+    let response = VerifyFileResponse {
+        complete: data.is_complete(),
+        integrity_ok: data.integrity_ok(),
+        frame_count: data.frames.len(),
+    };
+    state.logger.log_info(&format!(
+        "Verified {}: complete={}, integrity_ok={:?}",
+        sanitize_error_message(&filepath),
+        response.complete,
+        response.integrity_ok
+    ));
+    Ok(response)
+}
 
-    for param in request.parameters {
-        let (timestamps, values) = data.get_parameter_data(
-            param.index,
-            param.array_index,
-            time_range,
-            request.downsample,
-        );
+/// Load recordings of one flight that were split across files and join them,
+/// in the given order, into a single loaded recording
+#[tauri::command]
+async fn merge_files(
+    filepaths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<LoadFileResponse, String> {
+    state
+        .logger
+        .log_info(&format!("Merging {} recordings", filepaths.len()));
 
-        result.insert(
-            param.name.clone(),
-            ParameterData { timestamps, values },
-        );
+    let mut parts = Vec::with_capacity(filepaths.len());
+    for filepath in &filepaths {
+        let validated_path = match state.validate_path(filepath, state.max_file_size) {
+            Ok(path) => path,
+            Err(e) => {
+                let error_msg = format!("File validation failed: {}", e);
+                state.logger.log_error(&error_msg);
+                return Ok(LoadFileResponse::failure(sanitize_error_message(&error_msg)));
+            }
+        };
+        match xdr::XDRData::read(&validated_path, state.max_file_size) {
+            Ok(data) => parts.push(data),
+            Err(e) => {
+                let error_msg = format!("Failed to read XDR file: {}", e);
+                state.logger.log_error(&error_msg);
+                return Ok(LoadFileResponse::failure(sanitize_error_message(&e.to_string())));
+            }
+        }
     }
 
-    state.logger.log_debug(&format!("get_data returning {} parameter datasets", result.len()));
-    Ok(result)
+    match xdr::XDRData::concat(&parts) {
+        Ok(merged) => Ok(register_loaded_file(merged, &state)),
+        Err(e) => {
+            let error_msg = format!("Failed to merge recordings: {}", e);
+            state.logger.log_error(&error_msg);
+            Ok(LoadFileResponse::failure(sanitize_error_message(&error_msg)))
+        }
+    }
 }
 
+/// Replace the display name overrides (keyed by dataref name or substring)
+/// and return the loaded file's parameters relabelled with them
 #[tauri::command]
-async fn get_statistics(
-    request: GetStatisticsRequest,
+async fn set_display_names(
+    overrides: HashMap<String, String>,
     state: State<'_, AppState>,
-) -> Result<Vec<xdr::Statistics>, String> {
-    let data_guard = state.xdr_data.lock().unwrap();
-    let data = data_guard
-        .as_ref()
-        .ok_or_else(|| "No file loaded".to_string())?;
+) -> Result<Vec<xdr::Parameter>, String> {
+    state
+        .logger
+        .log_info(&format!("Setting {} display name overrides", overrides.len()));
+    *state.display_name_overrides.lock().unwrap() = overrides.clone();
+
+    let mut files = state.files.lock().unwrap();
+    for data in files.files.values_mut() {
+        data.display_name_overrides = overrides.clone();
+    }
+    Ok(files
+        .current()
+        .map(|data| data.get_all_plottable_parameters())
+        .unwrap_or_default())
+}
 
-    let mut result = Vec::new();
+/// Set (or clear with `null`) the filter that drops sentinel and non-finite
+/// values from parameter data and statistics of every loaded file
+#[tauri::command]
+async fn set_nan_policy(
+    policy: Option<xdr::NanPolicy>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.logger.log_info(&format!("Setting invalid-value policy: {:?}", policy));
+    *state.nan_policy.lock().unwrap() = policy.clone();
 
-    for param in request.parameters {
-        if let Some(stats) = data.get_parameter_statistics(param.index, param.array_index) {
-            result.push(stats);
-        }
+    let mut files = state.files.lock().unwrap();
+    for data in files.files.values_mut() {
+        data.nan_policy = policy.clone();
     }
+    Ok(())
+}
 
-    Ok(result)
+/// Make a loaded recording the target of single-file commands
+#[tauri::command]
+async fn select_file(file_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    if !state.files.lock().unwrap().select(&file_id) {
+        return Err(format!("Unknown file id: {}", file_id));
+    }
+    Ok(())
 }
 
-#[derive(Debug, Serialize)]
-struct FlightPhase {
+#[derive(Debug, Deserialize)]
+struct CompareParameterRequest {
+    file_a: String,
+    file_b: String,
+    /// Dataref name, with an `[idx]` suffix for array elements
     name: String,
-    start_time: f32,
-    end_time: f32,
-    duration: f32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    average_altitude: Option<f64>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    average_speed: Option<f64>,
+    /// Grid spacing in seconds; defaults to the finer of the two recording intervals
+    interval: Option<f32>,
 }
 
 #[derive(Debug, Serialize)]
-struct ApproachAnalysis {
-    stable_approach: bool,
-    average_descent_rate: f64,
-    touchdown_speed: f64,
-    final_approach_altitude: f64,
+struct CompareParameterResponse {
+    timestamps: Vec<f32>,
+    /// Values of each recording on the shared grid; `null` outside its time span
+    values_a: Vec<Option<f64>>,
+    values_b: Vec<Option<f64>>,
 }
 
-#[derive(Debug, Serialize)]
-struct Anomaly {
-    timestamp: f32,
-    severity: String, // "low", "medium", "high"
-    description: String,
-    parameter: String,
-    value: f64,
+/// One parameter from two recordings, resampled onto a common time grid
+#[tauri::command]
+async fn compare_parameter(
+    request: CompareParameterRequest,
+    state: State<'_, AppState>,
+) -> Result<CompareParameterResponse, String> {
+    let files = state.files.lock().unwrap();
+    let lookup = |id: &str| {
+        let data = files.get(id).ok_or_else(|| format!("Unknown file id: {}", id))?;
+        let (index, array_index) = data
+            .resolve_parameter(&request.name)
+            .ok_or_else(|| format!("Parameter {} not found in {}", request.name, id))?;
+        Ok::<_, String>((data, index, array_index))
+    };
+    let (data_a, index_a, array_a) = lookup(&request.file_a)?;
+    let (data_b, index_b, array_b) = lookup(&request.file_b)?;
+
+    let interval = request.interval.unwrap_or_else(|| {
+        [data_a.header.interval, data_b.header.interval]
+            .into_iter()
+            .filter(|i| *i > 0.0)
+            .reduce(f32::min)
+            .unwrap_or(1.0)
+    });
+    if interval <= 0.0 || !interval.is_finite() {
+        return Err("Interval must be positive".to_string());
+    }
+
+    let span = |data: &xdr::XDRData| match (data.frames.first(), data.frames.last()) {
+        (Some(first), Some(last)) => Some((first.timestamp, last.timestamp)),
+        _ => None,
+    };
+    let (start, end) = match (span(data_a), span(data_b)) {
+        (Some(a), Some(b)) => (a.0.min(b.0), a.1.max(b.1)),
+        (Some(span), None) | (None, Some(span)) => span,
+        (None, None) => return Err("No flight data available".to_string()),
+    };
+
+    let steps = ((end - start) / interval).floor() as usize;
+    if steps >= xdr::MAX_RESAMPLE_POINTS {
+        return Err(format!(
+            "Interval {}s is too fine for a {}s span (at most {} points)",
+            interval,
+            end - start,
+            xdr::MAX_RESAMPLE_POINTS
+        ));
+    }
+    let timestamps: Vec<f32> = (0..=steps).map(|k| start + k as f32 * interval).collect();
+
+    Ok(CompareParameterResponse {
+        values_a: data_a.sample_at(index_a, array_a, &timestamps),
+        values_b: data_b.sample_at(index_b, array_b, &timestamps),
+        timestamps,
+    })
 }
 
-#[derive(Debug, Serialize)]
-struct FlightAnalysis {
+/// Plottable parameters grouped by function for the parameter picker tree
+#[tauri::command]
+async fn get_parameter_groups(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, Vec<xdr::Parameter>>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.categorize_datarefs())
+}
+
+/// Header and dataref names of a recording without reading its frames
+#[tauri::command]
+async fn peek_file(filepath: String, state: State<'_, AppState>) -> Result<PeekFileResponse, String> {
+    // Previewing never loads frames, so oversized files can still be listed
+    let validated_path = state.validate_path(&filepath, u64::MAX).map_err(|e| {
+        let error_msg = format!("File validation failed: {}", e);
+        state.logger.log_warning(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    let file_size = std::fs::metadata(&validated_path).map(|m| m.len()).unwrap_or(0);
+    let (header, datarefs) = xdr::XDRData::read_header_only(&validated_path).map_err(|e| {
+        let error_msg = format!("Failed to read XDR header: {}", e);
+        state.logger.log_warning(&error_msg);
+        sanitize_error_message(&e.to_string())
+    })?;
+
+    Ok(PeekFileResponse {
+        header,
+        datarefs: datarefs.into_iter().map(|dr| dr.name).collect(),
+        file_size,
+    })
+}
+
+/// Datarefs the loaded recording's level implies that it doesn't contain
+#[tauri::command]
+async fn get_level_completeness(state: State<'_, AppState>) -> Result<xdr::LevelReport, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.level_completeness())
+}
+
+/// Dataref schema differences between two recordings, read from their headers only
+#[tauri::command]
+async fn diff_files(
+    first_path: String,
+    second_path: String,
+    state: State<'_, AppState>,
+) -> Result<xdr::SchemaDiff, String> {
+    let read_datarefs = |filepath: &str| -> Result<Vec<xdr::DatarefDef>, String> {
+        // Only headers are read, so oversized files can still be compared
+        let validated_path = state.validate_path(filepath, u64::MAX).map_err(|e| {
+            let error_msg = format!("File validation failed: {}", e);
+            state.logger.log_warning(&error_msg);
+            sanitize_error_message(&error_msg)
+        })?;
+        let (_, datarefs) = xdr::XDRData::read_header_only(&validated_path).map_err(|e| {
+            let error_msg = format!("Failed to read XDR header: {}", e);
+            state.logger.log_warning(&error_msg);
+            sanitize_error_message(&e.to_string())
+        })?;
+        Ok(datarefs)
+    };
+
+    let first = read_datarefs(&first_path)?;
+    let second = read_datarefs(&second_path)?;
+    let diff = xdr::SchemaDiff::between(&first, &second);
+
+    state.logger.log_info(&format!(
+        "Schema diff: {} only in first, {} only in second, {} type and {} array size mismatches",
+        diff.only_in_first.len(),
+        diff.only_in_second.len(),
+        diff.type_mismatches.len(),
+        diff.array_size_mismatches.len()
+    ));
+
+    Ok(diff)
+}
+
+#[derive(Debug, Serialize)]
+struct PollFileResponse {
+    new_frames: usize,
+    frame_count: usize,
+    /// Whether the recording has been finished (footer written)
+    complete: bool,
+}
+
+/// Read frames appended to the current recording since the last load or poll,
+/// so the viewer can follow a flight that is still being recorded
+#[tauri::command]
+async fn poll_file(state: State<'_, AppState>) -> Result<PollFileResponse, String> {
+    let mut files = state.files.lock().unwrap();
+    let data = files
+        .current_mut()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let new_frames = data.read_appended_frames().map_err(|e| {
+        let error_msg = format!("Failed to poll file: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    if new_frames > 0 {
+        state.logger.log_debug(&format!("Read {} new frames", new_frames));
+    }
+
+    Ok(PollFileResponse {
+        new_frames,
+        frame_count: data.frames.len(),
+        complete: data.is_complete(),
+    })
+}
+
+/// Unload a recording by id, or the current one when no id is given
+#[tauri::command]
+async fn unload_file(
+    file_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<UnloadFileResponse, String> {
+    let previous = {
+        let mut files = state.files.lock().unwrap();
+        match file_id.or_else(|| files.current.clone()) {
+            Some(id) => files.remove(&id),
+            None => None,
+        }
+    };
+
+    match previous {
+        Some(data) => {
+            state.logger.log_info(&format!("Unloaded file: {} frames released", data.frames.len()));
+        }
+        None => {
+            state.logger.log_debug("unload_file called but no file loaded");
+        }
+    }
+
+    Ok(UnloadFileResponse { success: true })
+}
+
+#[tauri::command]
+async fn get_data(
+    request: GetDataRequest,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, ParameterData>, String> {
+    state.logger.log_debug(&format!("get_data called with {} parameters", request.parameters.len()));
+    
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| {
+            state.logger.log_warning("get_data called but no file loaded");
+            "No file loaded".to_string()
+        })?;
+
+    let time_range = request.time_range.as_ref().and_then(|tr| {
+        if tr.len() >= 2 {
+            Some((tr[0], tr[1]))
+        } else {
+            None
+        }
+    });
+
+    let mut result = HashMap::new();
+
+    for param in request.parameters {
+        let (timestamps, mut values) =
+            data.derived_series_downsampled(&param, time_range, request.downsample, request.downsample_mode);
+
+        let mut unit = None;
+        if request.normalize {
+            let (mean, std) = data.derived_z_score_basis(&param, time_range);
+            xdr::normalize(&mut values, mean, std);
+        } else if let (Some(system), Some(dr)) = (request.unit_system, data.datarefs.get(param.source.index)) {
+            if let Some((factor, label)) = units::conversion(&dr.name, system) {
+                // Every transform is linear, so the factor still applies; the
+                // label only holds for transforms that keep the source unit
+                values.iter_mut().for_each(|v| *v *= factor);
+                if !matches!(param.transform, xdr::Transform::Derivative | xdr::Transform::Integral) {
+                    unit = Some(label);
+                }
+            }
+        }
+
+        let datetimes = request.absolute_time.then(|| {
+            timestamps
+                .iter()
+                .map(|&t| data.datetime_at(t).map(|dt| iso8601(&dt)).unwrap_or_default())
+                .collect()
+        });
+
+        result.insert(
+            param.name(),
+            ParameterData { timestamps, values, unit, datetimes },
+        );
+    }
+
+    state.logger.log_debug(&format!("get_data returning {} parameter datasets", result.len()));
+    Ok(result)
+}
+
+/// Like `get_data`, addressed by dataref name. Unknown names map to `null`.
+#[tauri::command]
+async fn get_data_by_name(
+    request: GetDataByNameRequest,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, Option<ParameterData>>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let time_range = request.time_range.as_ref().and_then(|tr| {
+        if tr.len() >= 2 {
+            Some((tr[0], tr[1]))
+        } else {
+            None
+        }
+    });
+
+    let mut result = HashMap::new();
+
+    for name in request.names {
+        let series = data.get_parameter_data_by_name(&name, time_range, request.downsample);
+        if series.is_none() {
+            state.logger.log_warning(&format!("get_data_by_name: unknown parameter {}", name));
+        }
+
+        let entry = series.map(|(timestamps, values)| ParameterData {
+            timestamps,
+            values,
+            unit: None,
+            datetimes: None,
+        });
+        result.insert(name, entry);
+    }
+
+    Ok(result)
+}
+
+/// Frames where all conditions hold, e.g. gear down and altitude below 500
+#[tauri::command]
+async fn search_frames(
+    request: SearchFramesRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<xdr::FrameMatch>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let matches = data.search_frames(&request.conditions, request.limit)?;
+    state.logger.log_debug(&format!(
+        "search_frames: {} conditions matched {} frames",
+        request.conditions.len(),
+        matches.len()
+    ));
+    Ok(matches)
+}
+
+#[tauri::command]
+async fn get_statistics(
+    request: GetStatisticsRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<xdr::Statistics>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let time_range = request.time_range.as_ref().and_then(|tr| {
+        if tr.len() >= 2 {
+            Some((tr[0], tr[1]))
+        } else {
+            None
+        }
+    });
+
+    Ok(data.statistics_for(&request.parameters, time_range))
+}
+
+/// Statistics for each element of an array dataref, addressed by name
+#[tauri::command]
+async fn get_array_statistics(
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<xdr::Statistics>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let index = data
+        .datarefs
+        .iter()
+        .position(|dr| dr.name == name)
+        .ok_or_else(|| format!("Unknown dataref: {}", name))?;
+
+    Ok(data.array_statistics(index))
+}
+
+/// Statistics for every plottable parameter, computed in one pass
+#[tauri::command]
+async fn get_all_statistics(state: State<'_, AppState>) -> Result<Vec<xdr::Statistics>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let stats = data.all_statistics();
+    state
+        .logger
+        .log_debug(&format!("Computed statistics for {} parameters", stats.len()));
+    Ok(stats)
+}
+
+/// Parameters that hold one constant value for the whole recording
+#[tauri::command]
+async fn get_flat_datarefs(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let flat = data.flat_datarefs();
+    if !flat.is_empty() {
+        state
+            .logger
+            .log_info(&format!("{} parameters are constant for the whole recording", flat.len()));
+    }
+    Ok(flat)
+}
+
+#[derive(Debug, Deserialize)]
+struct GetHistogramRequest {
+    parameter: xdr::Parameter,
+    bins: usize,
+    /// Explicit `[min, max]` range for comparing flights on identical bins
+    range: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Serialize)]
+struct HistogramResponse {
+    edges: Vec<f64>,
+    counts: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetRollingStatisticsRequest {
+    parameter: xdr::Parameter,
+    window_secs: f32,
+}
+
+#[derive(Debug, Serialize)]
+struct RollingStatisticsPoint {
+    time: f32,
+    #[serde(flatten)]
+    statistics: xdr::Statistics,
+}
+
+#[tauri::command]
+async fn get_histogram(
+    request: GetHistogramRequest,
+    state: State<'_, AppState>,
+) -> Result<HistogramResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    if request.bins == 0 {
+        return Err("Bin count must be positive".to_string());
+    }
+
+    let (edges, counts) = data.histogram(
+        request.parameter.index,
+        request.parameter.array_index,
+        request.bins,
+        request.range,
+    );
+
+    Ok(HistogramResponse { edges, counts })
+}
+
+#[tauri::command]
+async fn get_rolling_statistics(
+    request: GetRollingStatisticsRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<RollingStatisticsPoint>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    if request.window_secs <= 0.0 {
+        return Err("Window length must be positive".to_string());
+    }
+
+    Ok(data
+        .rolling_statistics(request.parameter.index, request.parameter.array_index, request.window_secs)
+        .into_iter()
+        .map(|(time, statistics)| RollingStatisticsPoint { time, statistics })
+        .collect())
+}
+
+#[tauri::command]
+async fn get_crossings(
+    request: GetCrossingsRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<f32>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.threshold_crossings(
+        request.parameter.index,
+        request.parameter.array_index,
+        request.level,
+        request.direction,
+    ))
+}
+
+#[tauri::command]
+async fn get_threshold_integral(
+    request: GetThresholdIntegralRequest,
+    state: State<'_, AppState>,
+) -> Result<xdr::ThresholdIntegral, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.threshold_integral(
+        request.parameter.index,
+        request.parameter.array_index,
+        request.threshold,
+        request.direction,
+    ))
+}
+
+/// Savitzky–Golay smoothed series, in the parameter's own units
+#[tauri::command]
+async fn get_smoothed(
+    request: GetSmoothedRequest,
+    state: State<'_, AppState>,
+) -> Result<ParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (timestamps, values) = data.smooth_savgol(
+        request.parameter.index,
+        request.parameter.array_index,
+        request.window,
+        request.order,
+    )?;
+
+    Ok(ParameterData {
+        timestamps,
+        values,
+        unit: None,
+        datetimes: None,
+    })
+}
+
+#[tauri::command]
+async fn get_derivative(
+    parameter: xdr::Parameter,
+    state: State<'_, AppState>,
+) -> Result<DerivedParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (timestamps, values) = data.derivative(parameter.index, parameter.array_index);
+    // Rate of change of the recorded unit; empty when the unit is unknown
+    let unit = match data.datarefs.get(parameter.index).and_then(|dr| units::native_label(&dr.name)) {
+        Some(label) if label.ends_with("/s") => format!("{}²", label),
+        Some(label) => format!("{}/s", label),
+        None => String::new(),
+    };
+
+    Ok(DerivedParameterData {
+        name: format!("d({})/dt", parameter.name),
+        unit,
+        timestamps,
+        values,
+    })
+}
+
+#[tauri::command]
+async fn get_integral(
+    parameter: xdr::Parameter,
+    state: State<'_, AppState>,
+) -> Result<DerivedParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (timestamps, values) = data.integrate(parameter.index, parameter.array_index);
+    // Recorded unit times seconds (a speed integrates to a distance); empty
+    // when the unit is unknown
+    let unit = match data.datarefs.get(parameter.index).and_then(|dr| units::native_label(&dr.name)) {
+        Some(label) => match label.strip_suffix("/s") {
+            Some(distance) => distance.to_string(),
+            None => format!("{}·s", label),
+        },
+        None => String::new(),
+    };
+
+    Ok(DerivedParameterData {
+        name: format!("∫{} dt", parameter.name),
+        unit,
+        timestamps,
+        values,
+    })
+}
+
+#[tauri::command]
+async fn get_rate_of_turn(state: State<'_, AppState>) -> Result<DerivedParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (timestamps, values) = data
+        .rate_of_turn()
+        .ok_or_else(|| "Heading data not found".to_string())?;
+
+    Ok(DerivedParameterData {
+        name: "Rate of turn".to_string(),
+        unit: "deg/s".to_string(),
+        timestamps,
+        values,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct FlightPhase {
+    name: String,
+    start_time: f32,
+    end_time: f32,
+    duration: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_altitude: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_speed: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    landing_g_force: Option<f64>,
+    /// Fuel burned during the phase (fuel flow integrated over time)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fuel_used: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    average_fuel_flow: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApproachAnalysis {
+    stable_approach: bool,
+    average_descent_rate: f64,
+    touchdown_speed: f64,
+    final_approach_altitude: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Anomaly {
+    timestamp: f32,
+    /// Last sample of the event; equal to `timestamp` for a single sample
+    end_timestamp: f32,
+    /// Frame (data table row) at `timestamp`
+    frame_index: usize,
+    severity: String, // "low", "medium", "high"
+    description: String,
+    parameter: String,
+    value: f64,
+    /// Most extreme value (largest magnitude) during the event
+    peak_value: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct FlightAnalysis {
     phases: Vec<FlightPhase>,
     total_flight_time: f32,
     max_altitude: f64,
     max_speed: f64,
     average_fuel_flow: Option<f64>,
     landing_g_force: Option<f64>,
+    /// "smooth", "firm" or "hard" for the final touchdown
+    landing_quality: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_climb_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_descent_rate: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    approach_analysis: Option<ApproachAnalysis>,
+    anomalies: Vec<Anomaly>,
+}
+
+/// One entry of the chronological flight timeline, tagged by `type`
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TimelineEvent {
+    PhaseStart {
+        timestamp: f32,
+        phase: String,
+    },
+    PhaseEnd {
+        timestamp: f32,
+        phase: String,
+    },
+    /// Touchdown at the start of a landing phase; `quality` is only
+    /// classified for the final landing
+    Landing {
+        timestamp: f32,
+        g_force: Option<f64>,
+        quality: Option<String>,
+    },
+    Anomaly(Anomaly),
+}
+
+impl TimelineEvent {
+    fn timestamp(&self) -> f32 {
+        match self {
+            TimelineEvent::PhaseStart { timestamp, .. }
+            | TimelineEvent::PhaseEnd { timestamp, .. }
+            | TimelineEvent::Landing { timestamp, .. } => *timestamp,
+            TimelineEvent::Anomaly(anomaly) => anomaly.timestamp,
+        }
+    }
+}
+
+/// Flatten an analysis into one event stream sorted by time. At equal times
+/// a phase ends before the next one starts, and touchdowns and anomalies
+/// follow the phase boundaries.
+fn build_timeline(analysis: FlightAnalysis) -> Vec<TimelineEvent> {
+    let final_landing = analysis.phases.iter().rposition(|p| p.name == "Landing");
+
+    // (tie-break rank, event); the sort below is stable
+    let mut events = Vec::new();
+    for (i, phase) in analysis.phases.iter().enumerate() {
+        events.push((
+            1,
+            TimelineEvent::PhaseStart {
+                timestamp: phase.start_time,
+                phase: phase.name.clone(),
+            },
+        ));
+        // A zero-length phase must still end after it starts
+        let end_rank = if phase.end_time > phase.start_time { 0 } else { 1 };
+        events.push((
+            end_rank,
+            TimelineEvent::PhaseEnd {
+                timestamp: phase.end_time,
+                phase: phase.name.clone(),
+            },
+        ));
+        if phase.name == "Landing" {
+            events.push((
+                2,
+                TimelineEvent::Landing {
+                    timestamp: phase.start_time,
+                    g_force: phase.landing_g_force,
+                    quality: if Some(i) == final_landing {
+                        analysis.landing_quality.clone()
+                    } else {
+                        None
+                    },
+                },
+            ));
+        }
+    }
+    events.extend(analysis.anomalies.into_iter().map(|a| (3, TimelineEvent::Anomaly(a))));
+
+    events.sort_by(|(rank_a, a), (rank_b, b)| a.timestamp().total_cmp(&b.timestamp()).then(rank_a.cmp(rank_b)));
+    events.into_iter().map(|(_, event)| event).collect()
+}
+
+// Constants for flight phase detection
+const ALTITUDE_THRESHOLD_AGL: f64 = 10.0; // feet AGL threshold for takeoff/landing detection
+const LANDING_HYSTERESIS_AGL: f64 = 50.0; // feet above the threshold required before arming a landing
+const LANDING_ROLLOUT_SECS: f32 = 5.0; // seconds after touchdown included in the landing phase
+
+/// Limits used by flight analysis. Different aircraft categories need
+/// different values, so callers may override any of these.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct AnalysisThresholds {
+    /// Height AGL (feet) separating ground from flight for takeoff/landing detection
+    takeoff_landing_agl: f64,
+    /// Extra height (feet) above `takeoff_landing_agl` required before a landing is armed
+    landing_hysteresis_agl: f64,
+    /// Seconds after touchdown included in the landing phase
+    landing_rollout_secs: f32,
+    /// Descent rate (fpm, positive) beyond which an excessive-descent anomaly is raised
+    max_descent_rate: f64,
+    /// Positive G load above which a medium-severity anomaly is raised
+    g_warning: f64,
+    /// Positive G load above which the anomaly becomes high severity
+    g_critical: f64,
+    /// Negative G load below which a medium-severity anomaly is raised
+    negative_g_warning: f64,
+    /// Negative G load below which the anomaly becomes high severity
+    negative_g_critical: f64,
+    /// Seconds before the final landing analyzed as the approach
+    approach_window_secs: f32,
+    /// Slowest descent rate (fpm, positive) counted as a stable approach
+    stable_approach_min_descent: f64,
+    /// Fastest descent rate (fpm, positive) counted as a stable approach
+    stable_approach_max_descent: f64,
+    /// Fraction (0.0-1.0) of approach samples that must be stable
+    stable_approach_ratio: f64,
+    /// Indicated airspeed (knots) above which an overspeed anomaly is raised
+    overspeed_kias: f64,
+    /// Indicated airspeed (knots) below which in-flight samples count as low
+    low_airspeed_kias: f64,
+    /// Seconds low airspeed must persist in flight before it is flagged
+    low_airspeed_min_secs: f32,
+    /// Vertical speed band (fpm, +/-) treated as level cruise
+    cruise_vs_band: f64,
+    /// Seconds a new climb/cruise/descent state must persist before the phase changes
+    phase_min_dwell_secs: f32,
+    /// Window (seconds) of the moving average applied to vertical speed for phase detection
+    vs_smoothing_secs: f32,
+    /// Height AGL (feet) that must be descended through and climbed back above,
+    /// without touching down, for a go-around to be detected
+    go_around_agl: f64,
+    /// Absolute bank angle (degrees) above which a medium-severity anomaly is raised
+    bank_warning_deg: f64,
+    /// Absolute bank angle (degrees) above which the anomaly becomes high severity
+    bank_critical_deg: f64,
+    /// Absolute turn rate (degrees/second) above which an airborne anomaly is raised
+    max_turn_rate_dps: f64,
+    /// Angle of attack (degrees) above which an airborne approaching-stall
+    /// anomaly is raised
+    critical_aoa_deg: f64,
+    /// Angle of attack (degrees) above which the anomaly becomes high severity
+    stall_aoa_deg: f64,
+    /// Samples of the same anomaly less than this many seconds apart are
+    /// reported as one event
+    anomaly_merge_gap_secs: f32,
+    /// Groundspeed (knots) above which the aircraft counts as taxiing
+    taxi_min_speed_kts: f64,
+    /// Groundspeed (knots) from which on-ground movement counts as a takeoff
+    /// or landing roll rather than taxiing
+    taxi_max_speed_kts: f64,
+    /// Touchdown G load above which a landing is classified as firm
+    firm_landing_g: f64,
+    /// Touchdown G load above which a landing is classified as hard
+    hard_landing_g: f64,
+    /// Touchdown sink rate (fpm, positive) beyond which a landing is firm
+    firm_landing_fpm: f64,
+    /// Touchdown sink rate (fpm, positive) beyond which a landing is hard
+    hard_landing_fpm: f64,
+}
+
+impl Default for AnalysisThresholds {
+    fn default() -> Self {
+        AnalysisThresholds {
+            takeoff_landing_agl: ALTITUDE_THRESHOLD_AGL,
+            landing_hysteresis_agl: LANDING_HYSTERESIS_AGL,
+            landing_rollout_secs: LANDING_ROLLOUT_SECS,
+            max_descent_rate: 2000.0,
+            g_warning: 2.5,
+            g_critical: 3.0,
+            negative_g_warning: -1.0,
+            negative_g_critical: -1.5,
+            approach_window_secs: 120.0,
+            stable_approach_min_descent: 300.0,
+            stable_approach_max_descent: 1000.0,
+            stable_approach_ratio: 0.7,
+            overspeed_kias: 340.0,
+            low_airspeed_kias: 60.0,
+            low_airspeed_min_secs: 5.0,
+            cruise_vs_band: 300.0,
+            phase_min_dwell_secs: 30.0,
+            vs_smoothing_secs: 10.0,
+            go_around_agl: 200.0,
+            bank_warning_deg: 60.0,
+            bank_critical_deg: 75.0,
+            max_turn_rate_dps: 15.0,
+            critical_aoa_deg: 15.0,
+            stall_aoa_deg: 18.0,
+            anomaly_merge_gap_secs: 1.0,
+            taxi_min_speed_kts: 5.0,
+            taxi_max_speed_kts: 40.0,
+            firm_landing_g: 1.6,
+            hard_landing_g: 2.1,
+            firm_landing_fpm: 300.0,
+            hard_landing_fpm: 600.0,
+        }
+    }
+}
+
+/// Classify a touchdown from its G load and vertical speed (fpm, negative
+/// when descending). `None` when neither was recorded.
+fn classify_landing(g_force: Option<f64>, vspeed: Option<f64>, thresholds: &AnalysisThresholds) -> Option<String> {
+    if g_force.is_none() && vspeed.is_none() {
+        return None;
+    }
+    let g = g_force.unwrap_or(f64::NEG_INFINITY);
+    let sink_rate = vspeed.map_or(f64::NEG_INFINITY, |vs| -vs);
+
+    let quality = if g > thresholds.hard_landing_g || sink_rate > thresholds.hard_landing_fpm {
+        "hard"
+    } else if g > thresholds.firm_landing_g || sink_rate > thresholds.firm_landing_fpm {
+        "firm"
+    } else {
+        "smooth"
+    };
+    Some(quality.to_string())
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "high" => 2,
+        "medium" => 1,
+        _ => 0,
+    }
+}
+
+/// Merge per-sample anomalies of the same parameter and description into
+/// events, joining samples less than `gap_secs` apart. An event keeps its
+/// first sample's time and value, its worst severity and its peak value.
+/// The result is ordered by time.
+fn coalesce_anomalies(mut anomalies: Vec<Anomaly>, gap_secs: f32) -> Vec<Anomaly> {
+    anomalies.sort_by(|a, b| {
+        (&a.parameter, &a.description)
+            .cmp(&(&b.parameter, &b.description))
+            .then(a.timestamp.total_cmp(&b.timestamp))
+    });
+
+    let mut events: Vec<Anomaly> = Vec::new();
+    for anomaly in anomalies {
+        match events.last_mut() {
+            Some(event)
+                if event.parameter == anomaly.parameter
+                    && event.description == anomaly.description
+                    && anomaly.timestamp - event.end_timestamp < gap_secs =>
+            {
+                event.end_timestamp = anomaly.end_timestamp;
+                if anomaly.peak_value.abs() > event.peak_value.abs() {
+                    event.peak_value = anomaly.peak_value;
+                }
+                if severity_rank(&anomaly.severity) > severity_rank(&event.severity) {
+                    event.severity = anomaly.severity;
+                }
+            }
+            _ => events.push(anomaly),
+        }
+    }
+
+    events.sort_by(|a, b| a.timestamp.total_cmp(&b.timestamp));
+    events
+}
+
+/// A check run over a recording during flight analysis. Each rule carries
+/// the dataref indices and limits it needs, so new checks are added to the
+/// rule set rather than to `run_flight_analysis`.
+trait AnomalyRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly>;
+}
+
+/// Dataref indices the built-in anomaly rules read from
+struct AnomalyDatarefs {
+    vspeed: Option<usize>,
+    g_force: Option<usize>,
+    bank: Option<usize>,
+    airspeed: Option<usize>,
+    aoa: Option<usize>,
+}
+
+/// Anomaly for a single sample at time `t`
+fn sample_anomaly(
+    data: &xdr::XDRData,
+    t: f32,
+    severity: &str,
+    description: &str,
+    parameter: &str,
+    value: f64,
+) -> Anomaly {
+    Anomaly {
+        timestamp: t,
+        end_timestamp: t,
+        frame_index: data.frame_index_at_time(t),
+        severity: severity.to_string(),
+        description: description.to_string(),
+        parameter: parameter.to_string(),
+        value,
+        peak_value: value,
+    }
+}
+
+/// Airspeed or groundspeed samples in knots; true airspeed and groundspeed
+/// are recorded in m/s
+fn speed_knots(data: &xdr::XDRData, speed_idx: usize, time_range: Option<(f32, f32)>) -> (Vec<f32>, Vec<f64>) {
+    let name = &data.datarefs[speed_idx].name;
+    let (times, speeds) = data.get_parameter_data(speed_idx, 0, time_range, 1);
+    let knots = speeds
+        .into_iter()
+        .map(|v| units::convert_value(name, v, units::UnitSystem::Imperial))
+        .collect();
+    (times, knots)
+}
+
+/// Height above ground in feet, whatever unit the dataref records
+fn altitude_feet(data: &xdr::XDRData, alt_idx: usize, time_range: Option<(f32, f32)>) -> (Vec<f32>, Vec<f64>) {
+    let name = &data.datarefs[alt_idx].name;
+    let (times, altitudes) = data.get_parameter_data(alt_idx, 0, time_range, 1);
+    let feet = altitudes
+        .into_iter()
+        .map(|v| units::convert_value(name, v, units::UnitSystem::Imperial))
+        .collect();
+    (times, feet)
+}
+
+fn is_airborne(flight_windows: &[(f32, f32)], t: f32) -> bool {
+    flight_windows.iter().any(|&(start, end)| t >= start && t < end)
+}
+
+/// Vertical speed (fpm) below `-max_descent_rate`
+struct DescentRateRule {
+    vspeed_idx: usize,
+    max_descent_rate: f64,
+}
+
+impl AnomalyRule for DescentRateRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, vspeeds) = data.get_parameter_data(self.vspeed_idx, 0, None, 1);
+        times
+            .iter()
+            .zip(vspeeds)
+            .filter(|&(_, vspeed)| vspeed < -self.max_descent_rate)
+            .map(|(&t, vspeed)| sample_anomaly(data, t, "high", "Excessive descent rate", "Vertical Speed", vspeed))
+            .collect()
+    }
+}
+
+/// Positive or negative G load beyond the warning limits
+struct GForceRule {
+    g_idx: usize,
+    warning: f64,
+    critical: f64,
+    negative_warning: f64,
+    negative_critical: f64,
+}
+
+impl AnomalyRule for GForceRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, g_forces) = data.get_parameter_data(self.g_idx, 0, None, 1);
+        times
+            .iter()
+            .zip(g_forces)
+            .filter(|&(_, g)| g > self.warning || g < self.negative_warning)
+            .map(|(&t, g)| {
+                let severity = if g > self.critical || g < self.negative_critical {
+                    "high"
+                } else {
+                    "medium"
+                };
+                sample_anomaly(data, t, severity, "Excessive G-force", "G Load", g)
+            })
+            .collect()
+    }
+}
+
+/// Bank angle beyond the warning limit; the signed value keeps left/right
+struct BankAngleRule {
+    bank_idx: usize,
+    warning_deg: f64,
+    critical_deg: f64,
+}
+
+impl AnomalyRule for BankAngleRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, banks) = data.get_parameter_data(self.bank_idx, 0, None, 1);
+        times
+            .iter()
+            .zip(banks)
+            .filter(|&(_, bank)| bank.abs() > self.warning_deg)
+            .map(|(&t, bank)| {
+                let severity = if bank.abs() > self.critical_deg { "high" } else { "medium" };
+                sample_anomaly(data, t, severity, "Excessive bank angle", "Bank Angle", bank)
+            })
+            .collect()
+    }
+}
+
+/// High rate of turn while airborne (ground taxi turns are expected)
+struct TurnRateRule {
+    max_turn_rate_dps: f64,
+    flight_windows: Vec<(f32, f32)>,
+}
+
+impl AnomalyRule for TurnRateRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let Some((times, rates)) = data.rate_of_turn() else {
+            return Vec::new();
+        };
+        times
+            .iter()
+            .zip(rates)
+            .filter(|&(&t, rate)| is_airborne(&self.flight_windows, t) && rate.abs() > self.max_turn_rate_dps)
+            .map(|(&t, rate)| sample_anomaly(data, t, "medium", "High rate of turn", "Rate of Turn", rate))
+            .collect()
+    }
+}
+
+/// Airborne angle of attack beyond the critical angle, escalating past the
+/// stall angle. Ground samples are ignored since AoA is meaningless at rest.
+struct AngleOfAttackRule {
+    aoa_idx: usize,
+    critical_deg: f64,
+    stall_deg: f64,
+    flight_windows: Vec<(f32, f32)>,
+}
+
+impl AnomalyRule for AngleOfAttackRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, angles) = data.get_parameter_data(self.aoa_idx, 0, None, 1);
+        times
+            .iter()
+            .zip(angles)
+            .filter(|&(&t, aoa)| is_airborne(&self.flight_windows, t) && aoa > self.critical_deg)
+            .map(|(&t, aoa)| {
+                let severity = if aoa > self.stall_deg { "high" } else { "medium" };
+                sample_anomaly(data, t, severity, "Approaching stall", "Angle of Attack", aoa)
+            })
+            .collect()
+    }
+}
+
+/// Airspeed (knots) above the overspeed limit
+struct OverspeedRule {
+    airspeed_idx: usize,
+    overspeed_kias: f64,
+}
+
+impl AnomalyRule for OverspeedRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, airspeeds) = speed_knots(data, self.airspeed_idx, None);
+        times
+            .iter()
+            .zip(airspeeds)
+            .filter(|&(_, ias)| ias > self.overspeed_kias)
+            .map(|(&t, ias)| sample_anomaly(data, t, "high", "Overspeed", "Airspeed", ias))
+            .collect()
+    }
+}
+
+/// Airborne airspeed below the limit for at least `min_secs`, reported once
+/// per episode at its start with the lowest airspeed seen
+struct LowAirspeedRule {
+    airspeed_idx: usize,
+    low_airspeed_kias: f64,
+    min_secs: f32,
+    flight_windows: Vec<(f32, f32)>,
+}
+
+impl AnomalyRule for LowAirspeedRule {
+    fn evaluate(&self, data: &xdr::XDRData) -> Vec<Anomaly> {
+        let (times, airspeeds) = speed_knots(data, self.airspeed_idx, None);
+        let mut anomalies = Vec::new();
+        // Current low-speed episode: (start time, lowest airspeed, already reported)
+        let mut episode: Option<(f32, f64, bool)> = None;
+
+        for (&t, ias) in times.iter().zip(airspeeds) {
+            if is_airborne(&self.flight_windows, t) && ias < self.low_airspeed_kias {
+                let (start, lowest, reported) = episode.get_or_insert((t, ias, false));
+                *lowest = lowest.min(ias);
+                if !*reported && t - *start >= self.min_secs {
+                    anomalies.push(sample_anomaly(
+                        data,
+                        *start,
+                        "medium",
+                        "Sustained low airspeed (possible stall)",
+                        "Airspeed",
+                        *lowest,
+                    ));
+                    *reported = true;
+                }
+            } else {
+                episode = None;
+            }
+        }
+
+        anomalies
+    }
+}
+
+/// The built-in rule set for the datarefs present in a recording
+fn anomaly_rules(
+    indices: &AnomalyDatarefs,
+    thresholds: &AnalysisThresholds,
+    flight_windows: &[(f32, f32)],
+) -> Vec<Box<dyn AnomalyRule>> {
+    let mut rules: Vec<Box<dyn AnomalyRule>> = Vec::new();
+
+    if let Some(vspeed_idx) = indices.vspeed {
+        rules.push(Box::new(DescentRateRule {
+            vspeed_idx,
+            max_descent_rate: thresholds.max_descent_rate,
+        }));
+    }
+    if let Some(g_idx) = indices.g_force {
+        rules.push(Box::new(GForceRule {
+            g_idx,
+            warning: thresholds.g_warning,
+            critical: thresholds.g_critical,
+            negative_warning: thresholds.negative_g_warning,
+            negative_critical: thresholds.negative_g_critical,
+        }));
+    }
+    if let Some(bank_idx) = indices.bank {
+        rules.push(Box::new(BankAngleRule {
+            bank_idx,
+            warning_deg: thresholds.bank_warning_deg,
+            critical_deg: thresholds.bank_critical_deg,
+        }));
+    }
+    if let Some(aoa_idx) = indices.aoa {
+        rules.push(Box::new(AngleOfAttackRule {
+            aoa_idx,
+            critical_deg: thresholds.critical_aoa_deg,
+            stall_deg: thresholds.stall_aoa_deg,
+            flight_windows: flight_windows.to_vec(),
+        }));
+    }
+    rules.push(Box::new(TurnRateRule {
+        max_turn_rate_dps: thresholds.max_turn_rate_dps,
+        flight_windows: flight_windows.to_vec(),
+    }));
+    if let Some(airspeed_idx) = indices.airspeed {
+        rules.push(Box::new(OverspeedRule {
+            airspeed_idx,
+            overspeed_kias: thresholds.overspeed_kias,
+        }));
+        rules.push(Box::new(LowAirspeedRule {
+            airspeed_idx,
+            low_airspeed_kias: thresholds.low_airspeed_kias,
+            min_secs: thresholds.low_airspeed_min_secs,
+            flight_windows: flight_windows.to_vec(),
+        }));
+    }
+
+    rules
+}
+
+/// Mean of a series, `None` when empty
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+/// Fuel flow summed across every engine of a per-engine array dataref
+fn total_fuel_flow(data: &xdr::XDRData, fuel_flow_idx: usize, time_range: Option<(f32, f32)>) -> (Vec<f32>, Vec<f64>) {
+    let engines = (data.datarefs[fuel_flow_idx].array_size as usize).max(1);
+    let (times, mut total) = data.get_parameter_data(fuel_flow_idx, 0, time_range, 1);
+    for engine in 1..engines {
+        let (_, flows) = data.get_parameter_data(fuel_flow_idx, engine, time_range, 1);
+        for (sum, flow) in total.iter_mut().zip(flows) {
+            *sum += flow;
+        }
+    }
+    (times, total)
+}
+
+/// Trapezoidal integral of a series over its timestamps (value-units x seconds)
+fn trapezoid(times: &[f32], values: &[f64]) -> f64 {
+    times
+        .windows(2)
+        .zip(values.windows(2))
+        .map(|(t, v)| (t[1] - t[0]).max(0.0) as f64 * (v[0] + v[1]) / 2.0)
+        .sum()
+}
+
+/// Centered moving average over a time window (seconds)
+fn moving_average(times: &[f32], values: &[f64], window_secs: f32) -> Vec<f64> {
+    let n = times.len().min(values.len());
+    let half = window_secs / 2.0;
+    let mut smoothed = Vec::with_capacity(n);
+    let (mut lo, mut hi) = (0, 0);
+    let mut sum = 0.0;
+
+    for i in 0..n {
+        while hi < n && times[hi] <= times[i] + half {
+            sum += values[hi];
+            hi += 1;
+        }
+        while lo < i && times[lo] < times[i] - half {
+            sum -= values[lo];
+            lo += 1;
+        }
+        smoothed.push(sum / (hi - lo) as f64);
+    }
+
+    smoothed
+}
+
+/// Split a time window into Climb/Cruise/Descent segments from smoothed vertical
+/// speed. A new state must persist for `min_dwell_secs` before it is committed.
+fn segment_vertical_phases(
+    times: &[f32],
+    vspeeds: &[f64],
+    (window_start, window_end): (f32, f32),
+    cruise_band: f64,
+    min_dwell_secs: f32,
+) -> Vec<(&'static str, f32, f32)> {
+    let classify = |vs: f64| {
+        if vs > cruise_band {
+            "Climb"
+        } else if vs < -cruise_band {
+            "Descent"
+        } else {
+            "Cruise"
+        }
+    };
+
+    let mut segments = Vec::new();
+    // (state, start time) of the committed phase and of a pending change
+    let mut current: Option<(&'static str, f32)> = None;
+    let mut pending: Option<(&'static str, f32)> = None;
+
+    for (&t, &vs) in times.iter().zip(vspeeds) {
+        if t < window_start || t >= window_end {
+            continue;
+        }
+        let state = classify(vs);
+
+        match current {
+            None => current = Some((state, window_start)),
+            Some((current_state, _)) if current_state == state => pending = None,
+            Some((current_state, current_start)) => match pending {
+                Some((pending_state, pending_start)) if pending_state == state => {
+                    if t - pending_start >= min_dwell_secs {
+                        segments.push((current_state, current_start, pending_start));
+                        current = Some((state, pending_start));
+                        pending = None;
+                    }
+                }
+                _ => pending = Some((state, t)),
+            },
+        }
+    }
+
+    if let Some((state, start)) = current {
+        segments.push((state, start, window_end));
+    }
+
+    segments
+}
+
+/// On-ground windows of a recording spanning `first_time..last_time`, split
+/// by the Takeoff and Landing phases, as (start, end, after a landing, before
+/// a takeoff)
+fn ground_windows(phases: &[FlightPhase], first_time: f32, last_time: f32) -> Vec<(f32, f32, bool, bool)> {
+    let mut windows = Vec::new();
+    let mut ground_since = Some((first_time, false));
+    for phase in phases {
+        match phase.name.as_str() {
+            "Takeoff" => {
+                if let Some((start, after_landing)) = ground_since.take() {
+                    windows.push((start, phase.start_time, after_landing, true));
+                }
+            }
+            "Landing" => ground_since = Some((phase.end_time, true)),
+            _ => {}
+        }
+    }
+    if let Some((start, after_landing)) = ground_since {
+        windows.push((start, last_time, after_landing, false));
+    }
+    windows
+}
+
+/// Taxi segments in an on-ground window: the span from the first to the last
+/// sample moving at taxi speed. After a full-stop landing with a later
+/// takeoff, the window is split at the longest stop into Taxi-In and Taxi-Out.
+fn segment_taxi_phases(
+    times: &[f32],
+    speeds_kts: &[f64],
+    (window_start, window_end): (f32, f32),
+    (after_landing, before_takeoff): (bool, bool),
+    (min_speed, max_speed): (f64, f64),
+) -> Vec<(&'static str, f32, f32)> {
+    let moving: Vec<f32> = times
+        .iter()
+        .zip(speeds_kts)
+        .filter(|&(&t, &v)| t >= window_start && t < window_end && v >= min_speed && v < max_speed)
+        .map(|(&t, _)| t)
+        .collect();
+    if moving.len() < 2 {
+        return Vec::new();
+    }
+
+    let span = |name, samples: &[f32]| match samples {
+        [first, .., last] => Some((name, *first, *last)),
+        _ => None,
+    };
+    let segments = if after_landing && before_takeoff {
+        let split = (1..moving.len())
+            .max_by(|&a, &b| (moving[a] - moving[a - 1]).total_cmp(&(moving[b] - moving[b - 1])))
+            .unwrap_or(moving.len());
+        vec![span("Taxi-In", &moving[..split]), span("Taxi-Out", &moving[split..])]
+    } else if after_landing {
+        vec![span("Taxi-In", &moving)]
+    } else {
+        vec![span("Taxi-Out", &moving)]
+    };
+
+    segments.into_iter().flatten().collect()
+}
+
+#[tauri::command]
+async fn get_analysis_thresholds() -> Result<AnalysisThresholds, String> {
+    Ok(AnalysisThresholds::default())
+}
+
+#[tauri::command]
+async fn analyze_flight(
+    thresholds: Option<AnalysisThresholds>,
+    state: State<'_, AppState>,
+) -> Result<FlightAnalysis, String> {
+    state.logger.log_info("Starting flight analysis");
+    let thresholds = thresholds.unwrap_or_default();
+    
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| {
+            state.logger.log_warning("analyze_flight called but no file loaded");
+            "No file loaded".to_string()
+        })?;
+
+    let analysis = run_flight_analysis(data, &thresholds).inspect_err(|_| {
+        state.logger.log_warning("Flight analysis attempted on empty data");
+    })?;
+
+    state.logger.log_info(&format!(
+        "Flight analysis completed: {} phases, {} anomalies detected",
+        analysis.phases.len(),
+        analysis.anomalies.len()
+    ));
+
+    Ok(analysis)
+}
+
+/// The flight analysis as one chronological event stream for a timeline view
+#[tauri::command]
+async fn analyze_flight_timeline(
+    thresholds: Option<AnalysisThresholds>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TimelineEvent>, String> {
+    let thresholds = thresholds.unwrap_or_default();
+
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let timeline = build_timeline(run_flight_analysis(data, &thresholds)?);
+    state
+        .logger
+        .log_info(&format!("Flight timeline built: {} events", timeline.len()));
+    Ok(timeline)
+}
+
+/// Detect flight phases, approach quality and anomalies in a recording
+fn run_flight_analysis(
+    data: &xdr::XDRData,
+    thresholds: &AnalysisThresholds,
+) -> Result<FlightAnalysis, String> {
+    if data.frames.is_empty() {
+        return Err("No flight data available".to_string());
+    }
+
+    // Find altitude and speed datarefs
+    let mut alt_idx = None;
+    let mut speed_idx = None;
+    let vspeed_idx = data.find_vertical_speed_dataref();
+    let mut fuel_flow_idx = None;
+    let mut g_force_idx = None;
+    let mut airspeed_idx = None;
+    let mut bank_idx = None;
+    let mut aoa_idx = None;
+
+    for (i, dr) in data.datarefs.iter().enumerate() {
+        let name = dr.name.to_lowercase();
+        let is_airspeed = xdr::is_airspeed_name(&name);
+        // Aircraft attitude only, not control inputs or autopilot targets
+        let is_bank = name.ends_with("position/phi")
+            || ((name.contains("roll") || name.contains("bank"))
+                && !["yoke", "ratio", "trim", "director", "autopilot"].iter().any(|x| name.contains(x)));
+        // Measured angle of attack, not aircraft config limits or warning flags
+        let is_aoa = (name.contains("alpha") || name.contains("aoa"))
+            && !["acf_", "warn", "autopilot"].iter().any(|x| name.contains(x));
+        if xdr::is_agl_name(&name) {
+            alt_idx = Some(i);
+        } else if is_airspeed {
+            // Prefer indicated airspeed over true airspeed when both are recorded
+            if airspeed_idx.is_none() || name.contains("indicated") || name.contains("kias") {
+                airspeed_idx = Some(i);
+            }
+        } else if name.contains("groundspeed") || name.contains("ground_speed") {
+            speed_idx = Some(i);
+        } else if name.contains("fuel_flow") || name.contains("engn_ff_") {
+            fuel_flow_idx = Some(i);
+        } else if xdr::is_g_load_name(&name) {
+            g_force_idx = Some(i);
+        } else if is_bank {
+            bank_idx = Some(i);
+        } else if is_aoa {
+            aoa_idx = Some(i);
+        }
+    }
+
+    let mut phases: Vec<FlightPhase> = Vec::new();
+    let mut max_altitude = 0.0;
+    let mut max_speed = 0.0;
+    let mut landing_g = None;
+    let mut landing_quality = None;
+    let mut altitudes = Vec::new(); // Store for later use
+
+    // Get altitude and speed data if available
+    if let Some(alt_i) = alt_idx {
+        let (_, alts) = altitude_feet(data, alt_i, None);
+        altitudes = alts.clone();
+        max_altitude = altitudes.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        
+        // Detect every takeoff/landing transition (touch-and-go and circuits)
+        let mut in_flight = false;
+        // A landing is only armed once the aircraft has climbed clear of the
+        // threshold, so sensor noise on the runway can't create phantom phases
+        let mut landing_armed = false;
+        // Go-around tracking: whether the aircraft is above the go-around
+        // height, and (entry time, descent seen) once it has dipped below it
+        let mut above_go_around = false;
+        let mut go_around_entry: Option<(f32, bool)> = None;
+        // Thresholds are in feet, whatever unit the altitude dataref records
+        let alt_name = &data.datarefs[alt_i].name;
+
+        for (i, frame) in data.frames.iter().enumerate() {
+            let alt = xdr::XDRData::frame_value(frame, alt_i, 0)
+                .map_or(0.0, |v| units::convert_value(alt_name, v, units::UnitSystem::Imperial));
+
+            if !in_flight {
+                if alt > thresholds.takeoff_landing_agl {
+                    // Takeoff detected; a previous landing roll ends here at the latest
+                    if let Some(last_phase) = phases.last_mut() {
+                        if last_phase.end_time > frame.timestamp {
+                            last_phase.end_time = frame.timestamp;
+                            last_phase.duration = frame.timestamp - last_phase.start_time;
+                        }
+                    }
+
+                    in_flight = true;
+                    landing_armed = false;
+                    phases.push(FlightPhase {
+                        name: "Takeoff".to_string(),
+                        start_time: frame.timestamp,
+                        end_time: frame.timestamp,
+                        duration: 0.0,
+                        average_altitude: None,
+                        average_speed: None,
+                        landing_g_force: None,
+                        fuel_used: None,
+                        average_fuel_flow: None,
+                    });
+                }
+                continue;
+            }
+
+            if alt > thresholds.takeoff_landing_agl + thresholds.landing_hysteresis_agl {
+                landing_armed = true;
+            }
+
+            let vspeed = vspeed_idx.and_then(|vs_i| xdr::XDRData::frame_value(frame, vs_i, 0));
+            if alt >= thresholds.go_around_agl {
+                if let Some((entry_time, descended)) = go_around_entry.take() {
+                    // Climbed back out without touching down; a descent-to-climb
+                    // reversal in vertical speed corroborates it when available
+                    let reversed = descended && vspeed.is_some_and(|vs| vs > 0.0);
+                    if vspeed_idx.is_none() || reversed {
+                        phases.push(FlightPhase {
+                            name: "Go-Around".to_string(),
+                            start_time: entry_time,
+                            end_time: frame.timestamp,
+                            duration: frame.timestamp - entry_time,
+                            average_altitude: None,
+                            average_speed: None,
+                            landing_g_force: None,
+                            fuel_used: None,
+                            average_fuel_flow: None,
+                        });
+                    }
+                }
+                above_go_around = true;
+            } else if alt >= thresholds.takeoff_landing_agl {
+                if above_go_around && landing_armed {
+                    go_around_entry = Some((frame.timestamp, false));
+                }
+                above_go_around = false;
+                if let Some((_, descended)) = go_around_entry.as_mut() {
+                    if vspeed.is_some_and(|vs| vs < 0.0) {
+                        *descended = true;
+                    }
+                }
+            }
+
+            if alt >= thresholds.takeoff_landing_agl {
+                continue;
+            }
+
+            in_flight = false;
+            above_go_around = false;
+            go_around_entry = None;
+
+            if !landing_armed {
+                // Dipped back below the threshold without ever climbing away:
+                // runway noise rather than a real takeoff
+                phases.pop();
+                continue;
+            }
+
+            // Landing detected; the airborne phase began at the last takeoff
+            if let Some(last_phase) = phases.iter_mut().rev().find(|p| p.name == "Takeoff") {
+                last_phase.end_time = frame.timestamp;
+                last_phase.duration = frame.timestamp - last_phase.start_time;
+            }
+
+            let landing_start = frame.timestamp;
+            let landing_g_force = g_force_idx.and_then(|g_i| xdr::XDRData::frame_value(frame, g_i, 0));
+
+            // The landing phase covers the rollout until we're clearly on the ground
+            let mut landing_end = landing_start;
+            for next_frame in &data.frames[i + 1..] {
+                landing_end = next_frame.timestamp;
+                if next_frame.timestamp - landing_start > thresholds.landing_rollout_secs {
+                    break;
+                }
+            }
+
+            phases.push(FlightPhase {
+                name: "Landing".to_string(),
+                start_time: landing_start,
+                end_time: landing_end,
+                duration: landing_end - landing_start,
+                average_altitude: None,
+                average_speed: None,
+                landing_g_force,
+                fuel_used: None,
+                average_fuel_flow: None,
+            });
+
+            // Report the G-force and quality of the final touchdown, both
+            // taken from the touchdown frame
+            if landing_g_force.is_some() || g_force_idx.is_none() {
+                landing_g = landing_g_force;
+                landing_quality = classify_landing(landing_g_force, vspeed, thresholds);
+            }
+        }
+    }
+
+    // Airborne windows, from each takeoff to the next landing
+    let last_time = data.frames.last().map(|f| f.timestamp).unwrap_or(0.0);
+    let mut flight_windows = Vec::new();
+    for (i, phase) in phases.iter().enumerate() {
+        if phase.name == "Takeoff" {
+            let end = phases[i + 1..]
+                .iter()
+                .find(|p| p.name == "Landing")
+                .map_or(last_time, |p| p.start_time);
+            flight_windows.push((phase.start_time, end));
+        }
+    }
+
+    // Segment the airborne time into climb, cruise and descent
+    if let Some(vs_i) = vspeed_idx {
+        let (times, vspeeds) = data.get_parameter_data(vs_i, 0, None, 1);
+        let smoothed = moving_average(&times, &vspeeds, thresholds.vs_smoothing_secs);
+
+        for &(window_start, window_end) in &flight_windows {
+            for (name, start_time, end_time) in segment_vertical_phases(
+                &times,
+                &smoothed,
+                (window_start, window_end),
+                thresholds.cruise_vs_band,
+                thresholds.phase_min_dwell_secs,
+            ) {
+                phases.push(FlightPhase {
+                    name: name.to_string(),
+                    start_time,
+                    end_time,
+                    duration: end_time - start_time,
+                    average_altitude: None,
+                    average_speed: None,
+                    landing_g_force: None,
+                    fuel_used: None,
+                    average_fuel_flow: None,
+                });
+            }
+        }
+    }
+
+    // Taxi before each takeoff and after each landing, from groundspeed in knots
+    if let Some(spd_i) = speed_idx {
+        let (times, speeds_kts) = speed_knots(data, spd_i, None);
+
+        let first_time = data.frames.first().map_or(0.0, |f| f.timestamp);
+        let windows = ground_windows(&phases, first_time, last_time);
+        for (window_start, window_end, after_landing, before_takeoff) in windows {
+            for (name, start_time, end_time) in segment_taxi_phases(
+                &times,
+                &speeds_kts,
+                (window_start, window_end),
+                (after_landing, before_takeoff),
+                (thresholds.taxi_min_speed_kts, thresholds.taxi_max_speed_kts),
+            ) {
+                phases.push(FlightPhase {
+                    name: name.to_string(),
+                    start_time,
+                    end_time,
+                    duration: end_time - start_time,
+                    average_altitude: None,
+                    average_speed: None,
+                    landing_g_force: None,
+                    fuel_used: None,
+                    average_fuel_flow: None,
+                });
+            }
+        }
+    }
+
+    phases.sort_by(|a, b| a.start_time.total_cmp(&b.start_time));
+
+    // Per-phase averages; fuel flow rates are per second unless the dataref says per hour
+    let fuel_flow_time_scale = match fuel_flow_idx.map(|ff_i| data.datarefs[ff_i].name.to_lowercase()) {
+        Some(name) if ["_hr", "hour", "gph", "pph"].iter().any(|x| name.contains(x)) => 1.0 / 3600.0,
+        _ => 1.0,
+    };
+    for phase in &mut phases {
+        let range = Some((phase.start_time, phase.end_time));
+        phase.average_altitude = alt_idx.and_then(|alt_i| mean(&altitude_feet(data, alt_i, range).1));
+        phase.average_speed = speed_idx.and_then(|spd_i| mean(&speed_knots(data, spd_i, range).1));
+
+        if let Some(ff_i) = fuel_flow_idx {
+            let (times, flows) = total_fuel_flow(data, ff_i, range);
+            phase.average_fuel_flow = mean(&flows);
+            phase.fuel_used = (!flows.is_empty()).then(|| trapezoid(&times, &flows) * fuel_flow_time_scale);
+        }
+    }
+
+    // Calculate max speed
+    if let Some(spd_i) = speed_idx {
+        let (_, speeds) = speed_knots(data, spd_i, None);
+        max_speed = speeds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    }
+
+    // Calculate max climb and descent rates
+    let (max_climb_rate, max_descent_rate) = if let Some(vs_i) = vspeed_idx {
+        let (_, vspeeds) = data.get_parameter_data(vs_i, 0, None, 1);
+        if !vspeeds.is_empty() {
+            let max_climb = vspeeds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let max_descent = vspeeds.iter().cloned().fold(f64::INFINITY, f64::min);
+            (Some(max_climb), Some(max_descent.abs()))
+        } else {
+            (None, None)
+        }
+    } else {
+        (None, None)
+    };
+
+    // Approach analysis (if landing detected)
+    let final_landing = phases.iter().rev().find(|p| p.name == "Landing");
+    let approach_analysis = if let (Some(landing), Some(vs_i), Some(spd_i)) = (final_landing, vspeed_idx, speed_idx) {
+        // Analyze the approach window before the final landing
+        let landing_time = landing.start_time;
+        let approach_start = (landing_time - thresholds.approach_window_secs).max(0.0);
+        
+        let (_, vspeeds) = data.get_parameter_data(vs_i, 0, Some((approach_start, landing_time)), 1);
+        let (_, speeds) = speed_knots(data, spd_i, Some((approach_start, landing_time)));
+        
+        if !vspeeds.is_empty() && !speeds.is_empty() {
+            let avg_descent = vspeeds.iter().sum::<f64>() / vspeeds.len() as f64;
+            let touchdown_spd = speeds.last().copied().unwrap_or(0.0);
+            
+            // Check for stable approach (descent rate within the stable band)
+            // Use floating point for accurate percentage calculation
+            let stable_count = vspeeds
+                .iter()
+                .filter(|&&v| {
+                    v < -thresholds.stable_approach_min_descent
+                        && v > -thresholds.stable_approach_max_descent
+                })
+                .count();
+            let stable = stable_count > (vspeeds.len() as f64 * thresholds.stable_approach_ratio) as usize;
+            
+            // Safely calculate final approach altitude
+            let final_alt_samples = altitudes.len().min(10);
+            let final_approach_altitude = if final_alt_samples > 0 {
+                altitudes.iter().rev().take(final_alt_samples).sum::<f64>() / final_alt_samples as f64
+            } else {
+                0.0
+            };
+            
+            Some(ApproachAnalysis {
+                stable_approach: stable,
+                average_descent_rate: avg_descent,
+                touchdown_speed: touchdown_spd,
+                final_approach_altitude,
+            })
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    // Anomaly detection
+    let indices = AnomalyDatarefs {
+        vspeed: vspeed_idx,
+        g_force: g_force_idx,
+        bank: bank_idx,
+        airspeed: airspeed_idx,
+        aoa: aoa_idx,
+    };
+    let rules = anomaly_rules(&indices, thresholds, &flight_windows);
+    let anomalies = coalesce_anomalies(
+        rules.iter().flat_map(|rule| rule.evaluate(data)).collect(),
+        thresholds.anomaly_merge_gap_secs,
+    );
+
+    // Calculate average fuel flow
+    let average_fuel_flow = if let Some(ff_i) = fuel_flow_idx {
+        let (_, fuel_flows) = total_fuel_flow(data, ff_i, None);
+        if !fuel_flows.is_empty() {
+            // Ensure the sum has an explicit numeric type
+            let fuel_flow_sum: f64 = fuel_flows.iter().copied().sum::<f64>();
+            let fuel_flow_count = fuel_flows.len();
+            Some(fuel_flow_sum / fuel_flow_count as f64)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let total_time = if !data.frames.is_empty() {
+        data.frames.last().unwrap().timestamp - data.frames.first().unwrap().timestamp
+    } else {
+        0.0
+    };
+
+    Ok(FlightAnalysis {
+        phases,
+        total_flight_time: total_time,
+        max_altitude,
+        max_speed,
+        average_fuel_flow,
+        landing_g_force: landing_g,
+        landing_quality,
+        max_climb_rate,
+        max_descent_rate,
+        approach_analysis,
+        anomalies,
+    })
+}
+
+#[tauri::command]
+async fn get_correlation(
+    request: GetCorrelationRequest,
+    state: State<'_, AppState>,
+) -> Result<CorrelationResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let names: Vec<String> = request.parameters.iter().map(|p| p.name()).collect();
+    let matrix = data.correlation_matrix(&request.parameters, request.resample_interval);
+
+    Ok(CorrelationResponse { matrix, names })
+}
+
+/// Correlation of one reference parameter with each of the others, strongest first
+#[tauri::command]
+async fn correlate_against(
+    request: CorrelateAgainstRequest,
+    state: State<'_, AppState>,
+) -> Result<Vec<ParameterCorrelation>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let reference = &request.reference;
+    Ok(data
+        .correlate_with(reference.index, reference.array_index, &request.others)
+        .into_iter()
+        .map(|(name, correlation)| ParameterCorrelation { name, correlation })
+        .collect())
+}
+
+#[derive(Debug, Deserialize)]
+struct GetCrossCorrelationRequest {
+    /// Control input (leading series)
+    input: xdr::Parameter,
+    /// Aircraft response (lagging series)
+    response: xdr::Parameter,
+    /// Largest lag tried in each direction, in frames
+    max_lag: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct CrossCorrelationResponse {
+    lags: Vec<i64>,
+    correlations: Vec<f64>,
+    /// Lag (frames) with the highest correlation
     #[serde(skip_serializing_if = "Option::is_none")]
-    max_climb_rate: Option<f64>,
+    best_lag: Option<i64>,
+}
+
+#[tauri::command]
+async fn get_cross_correlation(
+    request: GetCrossCorrelationRequest,
+    state: State<'_, AppState>,
+) -> Result<CrossCorrelationResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let result = data.cross_correlation(
+        request.input.index,
+        request.input.array_index,
+        request.response.index,
+        request.response.array_index,
+        request.max_lag,
+    );
+    let best_lag = result
+        .iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|&(lag, _)| lag);
+
+    Ok(CrossCorrelationResponse {
+        lags: result.iter().map(|&(lag, _)| lag).collect(),
+        correlations: result.iter().map(|&(_, corr)| corr).collect(),
+        best_lag,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct SpectrumResponse {
+    /// Bin frequencies in Hz
+    frequencies: Vec<f64>,
+    magnitudes: Vec<f64>,
+}
+
+#[tauri::command]
+async fn get_spectrum(
+    parameter: xdr::Parameter,
+    state: State<'_, AppState>,
+) -> Result<SpectrumResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (frequencies, magnitudes) = data.power_spectrum(parameter.index, parameter.array_index);
+    if frequencies.is_empty() {
+        return Err("Not enough samples for a frequency spectrum".to_string());
+    }
+
+    Ok(SpectrumResponse { frequencies, magnitudes })
+}
+
+/// Flight path, stride-downsampled to `max_points` (default 1000, `0` for the
+/// full track), or simplified with Douglas–Peucker when `epsilon` (meters of
+/// allowed deviation) is given
+#[tauri::command]
+async fn get_flight_path(
+    epsilon: Option<f64>,
+    max_points: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<FlightPathResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    if let Some(epsilon) = epsilon {
+        let points = data
+            .path_points()
+            .ok_or_else(|| "Position data not found".to_string())?;
+        let simplified = xdr::simplify_path(&points, epsilon);
+        state.logger.log_debug(&format!(
+            "Flight path simplified from {} to {} points",
+            points.len(),
+            simplified.len()
+        ));
+
+        return Ok(FlightPathResponse::new(
+            simplified.iter().map(|p| p.lat).collect(),
+            simplified.iter().map(|p| p.lon).collect(),
+            simplified.iter().map(|p| p.alt).collect(),
+            simplified.iter().map(|p| p.timestamp).collect(),
+        ));
+    }
+
+    match data.get_flight_path(max_points.unwrap_or(xdr::DEFAULT_FLIGHT_PATH_POINTS)) {
+        Some((lats, lons, alts, times)) => Ok(FlightPathResponse::new(lats, lons, alts, times)),
+        None => Err("Position data not found".to_string()),
+    }
+}
+
+/// Altitude over time, plus altitude over distance flown when position is recorded
+#[derive(Debug, Serialize)]
+struct AltitudeProfileResponse {
+    timestamps: Vec<f32>,
+    altitudes: Vec<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    max_descent_rate: Option<f64>,
+    distances_nm: Option<Vec<f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    approach_analysis: Option<ApproachAnalysis>,
-    anomalies: Vec<Anomaly>,
+    distance_altitudes: Option<Vec<f64>>,
 }
 
-// Constants for flight phase detection
-const ALTITUDE_THRESHOLD_AGL: f64 = 10.0; // feet AGL threshold for takeoff/landing detection
+#[tauri::command]
+async fn get_altitude_profile(state: State<'_, AppState>) -> Result<AltitudeProfileResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let (timestamps, altitudes) = data
+        .altitude_profile()
+        .ok_or_else(|| "Altitude data not found".to_string())?;
+    let (distances_nm, distance_altitudes) = data.altitude_distance_profile().unzip();
+
+    Ok(AltitudeProfileResponse {
+        timestamps,
+        altitudes,
+        distances_nm,
+        distance_altitudes,
+    })
+}
+
+#[tauri::command]
+async fn get_flight_path_metrics(state: State<'_, AppState>) -> Result<xdr::FlightPathMetrics, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    data.flight_path_metrics()
+        .ok_or_else(|| "Position data not found".to_string())
+}
+
+#[derive(Debug, Serialize)]
+struct TrackDeviationResponse {
+    timestamps: Vec<f32>,
+    distances_m: Vec<f64>,
+    max_deviation_m: f64,
+}
+
+/// Cross-track distance of the flight path from the straight course between
+/// its first and last fix
+#[tauri::command]
+async fn get_track_deviation(state: State<'_, AppState>) -> Result<TrackDeviationResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let deviation = data
+        .track_deviation()
+        .ok_or_else(|| "Position data not found".to_string())?;
+    let max_deviation_m = deviation.iter().map(|p| p.1).fold(0.0, f64::max);
+    let (timestamps, distances_m) = deviation.into_iter().unzip();
+
+    Ok(TrackDeviationResponse {
+        timestamps,
+        distances_m,
+        max_deviation_m,
+    })
+}
+
+#[tauri::command]
+async fn get_derived_groundspeed(state: State<'_, AppState>) -> Result<ParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    match data.get_derived_groundspeed() {
+        Some((timestamps, values)) => Ok(ParameterData {
+            timestamps,
+            values,
+            unit: Some("m/s"),
+            datetimes: None,
+        }),
+        None => Err("Position data not found".to_string()),
+    }
+}
+
+#[tauri::command]
+async fn get_specific_energy(state: State<'_, AppState>) -> Result<ParameterData, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    match data.specific_energy() {
+        Some((timestamps, values)) => Ok(ParameterData {
+            timestamps,
+            values,
+            unit: Some("ft"),
+            datetimes: None,
+        }),
+        None => Err("Altitude or true airspeed data not found".to_string()),
+    }
+}
+
+/// Estimated wind at one frame; speed in m/s, direction the wind blows from
+#[derive(Debug, Serialize)]
+struct WindEstimate {
+    timestamp: f32,
+    speed: f64,
+    direction: f64,
+}
+
+/// V-g diagram data: one (airspeed, G) point per frame plus the extremes
+#[derive(Debug, Serialize)]
+struct VgEnvelopeResponse {
+    airspeeds: Vec<f64>,
+    g_loads: Vec<f64>,
+    max_g: f64,
+    max_g_airspeed: f64,
+    min_g: f64,
+    min_g_airspeed: f64,
+}
+
+#[tauri::command]
+async fn get_vg_envelope(state: State<'_, AppState>) -> Result<VgEnvelopeResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let points = data
+        .vg_envelope()
+        .ok_or_else(|| "Airspeed or G-load data not found".to_string())?;
+    let max = points.iter().copied().max_by(|a, b| a.1.total_cmp(&b.1));
+    let min = points.iter().copied().min_by(|a, b| a.1.total_cmp(&b.1));
+    let ((max_g_airspeed, max_g), (min_g_airspeed, min_g)) = match (max, min) {
+        (Some(max), Some(min)) => (max, min),
+        _ => return Err("No samples with both airspeed and G-load".to_string()),
+    };
+
+    Ok(VgEnvelopeResponse {
+        airspeeds: points.iter().map(|p| p.0).collect(),
+        g_loads: points.iter().map(|p| p.1).collect(),
+        max_g,
+        max_g_airspeed,
+        min_g,
+        min_g_airspeed,
+    })
+}
+
+#[tauri::command]
+async fn get_wind_estimate(state: State<'_, AppState>) -> Result<Vec<WindEstimate>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let winds = data.estimate_wind().ok_or_else(|| {
+        "Wind estimation needs true airspeed, heading, groundspeed and track data".to_string()
+    })?;
+
+    Ok(winds
+        .into_iter()
+        .map(|(timestamp, speed, direction)| WindEstimate {
+            timestamp,
+            speed,
+            direction,
+        })
+        .collect())
+}
+
+/// Pick the values of a series at the given (monotonic) timeline timestamps.
+/// Timeline points without a matching sample are `None`.
+fn align_to_timeline(timeline: &[f32], times: &[f32], values: &[f64]) -> Vec<Option<f64>> {
+    let mut aligned = Vec::with_capacity(timeline.len());
+    let mut j = 0;
+
+    for &t in timeline {
+        while j < times.len() && times[j] < t {
+            j += 1;
+        }
+        if j < times.len() && j < values.len() && times[j] == t {
+            aligned.push(Some(values[j]));
+        } else {
+            aligned.push(None);
+        }
+    }
+
+    aligned
+}
 
 #[tauri::command]
-async fn analyze_flight(state: State<'_, AppState>) -> Result<FlightAnalysis, String> {
-    state.logger.log_info("Starting flight analysis");
-    
-    let data_guard = state.xdr_data.lock().unwrap();
+async fn get_speed_comparison(state: State<'_, AppState>) -> Result<SpeedComparisonResponse, String> {
+    let data_guard = state.files.lock().unwrap();
     let data = data_guard
-        .as_ref()
-        .ok_or_else(|| {
-            state.logger.log_warning("analyze_flight called but no file loaded");
-            "No file loaded".to_string()
-        })?;
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
 
-    if data.frames.is_empty() {
-        state.logger.log_warning("Flight analysis attempted on empty data");
-        return Err("No flight data available".to_string());
-    }
+    speed_comparison(data)
+}
+
+/// Groundspeed derived from position next to the recorded groundspeed and
+/// true airspeed, picked at the derived series' timestamps
+fn speed_comparison(data: &xdr::XDRData) -> Result<SpeedComparisonResponse, String> {
+    let (timestamps, derived_groundspeed) = data
+        .get_derived_groundspeed()
+        .ok_or_else(|| "Position data not found".to_string())?;
 
-    // Find altitude and speed datarefs
-    let mut alt_idx = None;
     let mut speed_idx = None;
-    let mut vspeed_idx = None;
-    let mut fuel_flow_idx = None;
-    let mut g_force_idx = None;
+    let mut tas_idx = None;
 
     for (i, dr) in data.datarefs.iter().enumerate() {
         let name = dr.name.to_lowercase();
-        if name.contains("altitude") && name.contains("agl") {
-            alt_idx = Some(i);
-        } else if name.contains("groundspeed") || name.contains("ground_speed") {
+        if name.contains("groundspeed") || name.contains("ground_speed") {
             speed_idx = Some(i);
-        } else if name.contains("vvi") || name.contains("vertical_speed") {
-            vspeed_idx = Some(i);
-        } else if name.contains("fuel_flow") {
-            fuel_flow_idx = Some(i);
-        } else if name.contains("g_nrml") || name.contains("g_load") {
-            g_force_idx = Some(i);
+        } else if name.contains("true_airspeed") {
+            tas_idx = Some(i);
         }
     }
 
-    let mut phases = Vec::new();
-    let mut max_altitude = 0.0;
-    let mut max_speed = 0.0;
-    let mut landing_g = None;
-    let mut altitudes = Vec::new(); // Store for later use
+    let aligned = |idx: usize| {
+        let (times, values) = data.get_parameter_data(idx, 0, None, 1);
+        align_to_timeline(&timestamps, &times, &values)
+    };
 
-    // Get altitude and speed data if available
-    if let Some(alt_i) = alt_idx {
-        let (_, alts) = data.get_parameter_data(alt_i, 0, None, 1);
-        altitudes = alts.clone();
-        max_altitude = altitudes.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        
-        // Detect flight phases based on altitude
-        let mut in_flight = false;
-        let total_frames = data.frames.len();
-        
-        for (i, frame) in data.frames.iter().enumerate() {
-            let alt = if alt_i < frame.values.len() {
-                match &frame.values[alt_i] {
-                    xdr::DataValue::Float(v) => *v as f64,
-                    xdr::DataValue::Int(v) => *v as f64,
-                    _ => 0.0,
-                }
-            } else {
-                0.0
+    let recorded_groundspeed = speed_idx.map(aligned);
+    let true_airspeed = tas_idx.map(aligned);
+
+    Ok(SpeedComparisonResponse {
+        timestamps,
+        derived_groundspeed,
+        recorded_groundspeed,
+        true_airspeed,
+    })
+}
+
+#[tauri::command]
+async fn get_table_data(
+    request: GetTableDataRequest,
+    state: State<'_, AppState>,
+) -> Result<TableDataResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let end = (request.start + request.count).min(data.frames.len());
+    let mut rows = Vec::new();
+
+    for i in request.start..end {
+        let frame = &data.frames[i];
+
+        rows.push(TableRow {
+            index: i,
+            timestamp: frame.timestamp,
+            datetime: request
+                .absolute_time
+                .then(|| data.absolute_timestamp(i).map(|dt| iso8601(&dt)))
+                .flatten(),
+            values: flatten_frame_values(data, frame),
+        });
+    }
+
+    Ok(TableDataResponse {
+        headers: table_headers(data),
+        rows,
+        total: data.frames.len(),
+    })
+}
+
+/// A parameter's value at `time`, interpolated between frames for smooth
+/// playback overlays; `null` outside the recording
+#[tauri::command]
+async fn get_value_at_time(
+    parameter: xdr::Parameter,
+    time: f32,
+    state: State<'_, AppState>,
+) -> Result<Option<f64>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.value_at_time(parameter.index, parameter.array_index, time))
+}
+
+#[tauri::command]
+async fn get_frame_at_time(
+    time: f32,
+    state: State<'_, AppState>,
+) -> Result<FrameAtTimeResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    if data.frames.is_empty() {
+        return Err("Recording contains no frames".to_string());
+    }
+
+    let index = data.frame_index_at_time(time);
+    let frame = &data.frames[index];
+
+    Ok(FrameAtTimeResponse {
+        headers: table_headers(data),
+        row: TableRow {
+            index,
+            timestamp: frame.timestamp,
+            datetime: None,
+            values: flatten_frame_values(data, frame),
+        },
+    })
+}
+
+#[tauri::command]
+async fn get_timestamp_report(state: State<'_, AppState>) -> Result<xdr::TimestampReport, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(data.validate_timestamps())
+}
+
+#[tauri::command]
+async fn get_gaps(
+    tolerance_factor: Option<f32>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TimeGap>, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let gaps = data.detect_gaps(tolerance_factor.unwrap_or(DEFAULT_GAP_TOLERANCE));
+    if !gaps.is_empty() {
+        state.logger.log_debug(&format!("Detected {} gaps in the timestamp series", gaps.len()));
+    }
+
+    Ok(gaps
+        .into_iter()
+        .map(|(start, end, duration)| TimeGap { start, end, duration })
+        .collect())
+}
+
+#[tauri::command]
+async fn get_table_deltas(
+    request: GetTableDeltasRequest,
+    state: State<'_, AppState>,
+) -> Result<TableDeltasResponse, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    Ok(table_deltas(data, &request))
+}
+
+/// A page of table rows holding only the cells that changed since the row before
+fn table_deltas(data: &xdr::XDRData, request: &GetTableDeltasRequest) -> TableDeltasResponse {
+    let end = (request.start + request.count).min(data.frames.len());
+    let mut rows = Vec::new();
+    let mut previous: Option<Vec<DataValueJson>> = None;
+
+    for i in request.start..end {
+        let frame = &data.frames[i];
+        let current = flatten_frame_values(data, frame);
+
+        let mut columns = Vec::new();
+        let mut values = Vec::new();
+
+        for (col, value) in current.iter().enumerate() {
+            // The first row of the page is emitted in full
+            let changed = match previous.as_ref().and_then(|prev| prev.get(col)) {
+                Some(prev) => value_changed(prev, value, request.float_tolerance),
+                None => true,
             };
+            if changed {
+                columns.push(col);
+                values.push(value.clone());
+            }
+        }
 
-            if !in_flight && alt > ALTITUDE_THRESHOLD_AGL {
-                // Takeoff detected
-                in_flight = true;
-                phases.push(FlightPhase {
-                    name: "Takeoff".to_string(),
-                    start_time: frame.timestamp,
-                    end_time: frame.timestamp,
-                    duration: 0.0,
-                    average_altitude: None,
-                    average_speed: None,
-                });
-            } else if in_flight && alt < ALTITUDE_THRESHOLD_AGL && i > total_frames / 2 {
-                // Landing detected - only in second half of flight to avoid false positives during takeoff
-                if let Some(last_phase) = phases.last_mut() {
-                    last_phase.end_time = frame.timestamp;
-                    last_phase.duration = frame.timestamp - last_phase.start_time;
+        rows.push(TableDeltaRow {
+            index: i,
+            timestamp: frame.timestamp,
+            columns,
+            values,
+        });
+        previous = Some(current);
+    }
+
+    TableDeltasResponse {
+        headers: table_headers(data),
+        rows,
+        total: data.frames.len(),
+    }
+}
+
+/// ISO-8601 UTC time with millisecond precision, e.g. `2024-05-01T12:00:00.250Z`
+fn iso8601(time: &chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Flatten a frame into table cells, expanding arrays into one cell per element.
+/// Cells the frame doesn't have (a short frame or array) are `Missing`, so the
+/// row always lines up with `table_headers`.
+fn flatten_frame_values(data: &xdr::XDRData, frame: &xdr::DataFrame) -> Vec<DataValueJson> {
+    let mut values = Vec::new();
+
+    for (i, dr) in data.datarefs.iter().enumerate() {
+        let row_len = values.len();
+        match frame.values.get(i) {
+            Some(xdr::DataValue::Float(v)) => values.push(DataValueJson::Float(*v)),
+            Some(xdr::DataValue::Int(v)) => values.push(DataValueJson::Int(*v)),
+            Some(xdr::DataValue::String(v)) => values.push(DataValueJson::String(v.clone())),
+            Some(xdr::DataValue::FloatArray(arr)) => {
+                for v in arr {
+                    values.push(DataValueJson::Float(*v));
                 }
-                
-                let landing_start = frame.timestamp;
-                phases.push(FlightPhase {
-                    name: "Landing".to_string(),
-                    start_time: landing_start,
-                    end_time: landing_start,
-                    duration: 0.0,
-                    average_altitude: None,
-                    average_speed: None,
-                });
-                
-                // Continue to find landing end and calculate duration
-                let landing_phase_idx = phases.len() - 1;
-                for j in (i + 1)..data.frames.len() {
-                    if let Some(next_frame) = data.frames.get(j) {
-                        phases[landing_phase_idx].end_time = next_frame.timestamp;
-                        phases[landing_phase_idx].duration = next_frame.timestamp - landing_start;
-                        
-                        // Stop updating once we're clearly on the ground (5+ seconds after touchdown)
-                        if next_frame.timestamp - landing_start > 5.0 {
-                            break;
-                        }
-                    }
+            }
+            Some(xdr::DataValue::IntArray(arr)) => {
+                for v in arr {
+                    values.push(DataValueJson::Int(*v));
                 }
-                
-                // Record landing G-force if available
-                if let Some(g_i) = g_force_idx {
-                    if g_i < frame.values.len() {
-                        if let xdr::DataValue::Float(v) = &frame.values[g_i] {
-                            landing_g = Some(*v as f64);
-                        }
-                    }
+            }
+            Some(xdr::DataValue::Double(v)) => values.push(DataValueJson::Double(*v)),
+            Some(xdr::DataValue::DoubleArray(arr)) => {
+                for v in arr {
+                    values.push(DataValueJson::Double(*v));
+                }
+            }
+            Some(xdr::DataValue::StringArray(arr)) => {
+                for v in arr {
+                    values.push(DataValueJson::String(v.clone()));
                 }
-                
-                break; // Only detect first landing
             }
+            None => {}
         }
+        let width = (dr.array_size as usize).max(1);
+        values.resize(row_len + width, DataValueJson::Missing);
     }
 
-    // Calculate max speed
-    if let Some(spd_i) = speed_idx {
-        let (_, speeds) = data.get_parameter_data(spd_i, 0, None, 1);
-        max_speed = speeds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    }
+    values
+}
 
-    // Calculate max climb and descent rates
-    let (max_climb_rate, max_descent_rate) = if let Some(vs_i) = vspeed_idx {
-        let (_, vspeeds) = data.get_parameter_data(vs_i, 0, None, 1);
-        if !vspeeds.is_empty() {
-            let max_climb = vspeeds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-            let max_descent = vspeeds.iter().cloned().fold(f64::INFINITY, f64::min);
-            (Some(max_climb), Some(max_descent.abs()))
+/// Table column headers matching `flatten_frame_values`
+fn table_headers(data: &xdr::XDRData) -> Vec<String> {
+    let mut headers = vec!["Index".to_string(), "Timestamp".to_string()];
+    for (dr, name) in data.datarefs.iter().zip(data.dataref_display_names()) {
+        if dr.array_size > 0 {
+            for j in 0..dr.array_size {
+                headers.push(format!("{}[{}]", name, j));
+            }
         } else {
-            (None, None)
+            headers.push(name);
         }
-    } else {
-        (None, None)
-    };
+    }
+    headers
+}
 
-    // Approach analysis (if landing detected)
-    let approach_analysis = if phases.iter().any(|p| p.name == "Landing") && vspeed_idx.is_some() && speed_idx.is_some() {
-        let vs_i = vspeed_idx.unwrap();
-        let spd_i = speed_idx.unwrap();
-        
-        // Analyze last 2 minutes before landing
-        let landing_time = phases.iter().find(|p| p.name == "Landing").unwrap().start_time;
-        let approach_start = (landing_time - 120.0).max(0.0);
-        
-        let (_, vspeeds) = data.get_parameter_data(vs_i, 0, Some((approach_start, landing_time)), 1);
-        let (_, speeds) = data.get_parameter_data(spd_i, 0, Some((approach_start, landing_time)), 1);
-        
-        if !vspeeds.is_empty() && !speeds.is_empty() {
-            let avg_descent = vspeeds.iter().sum::<f64>() / vspeeds.len() as f64;
-            let touchdown_spd = speeds.last().copied().unwrap_or(0.0);
-            
-            // Check for stable approach (descent rate between 300-1000 fpm)
-            // Use floating point for accurate percentage calculation
-            let stable_count = vspeeds.iter().filter(|&&v| v < -300.0 && v > -1000.0).count();
-            let stable = stable_count > (vspeeds.len() as f64 * 0.7) as usize; // 70% of approach should be stable
-            
-            // Safely calculate final approach altitude
-            let final_alt_samples = altitudes.len().min(10);
-            let final_approach_altitude = if final_alt_samples > 0 {
-                altitudes.iter().rev().take(final_alt_samples).sum::<f64>() / final_alt_samples as f64
+/// Whether a table cell differs from the previous row's cell.
+/// Floats compare within `float_tolerance`; ints and strings must match exactly.
+fn value_changed(prev: &DataValueJson, current: &DataValueJson, float_tolerance: f32) -> bool {
+    match (prev, current) {
+        (DataValueJson::Float(a), DataValueJson::Float(b)) => {
+            if a.is_nan() || b.is_nan() {
+                a.is_nan() != b.is_nan()
             } else {
-                0.0
-            };
-            
-            Some(ApproachAnalysis {
-                stable_approach: stable,
-                average_descent_rate: avg_descent,
-                touchdown_speed: touchdown_spd,
-                final_approach_altitude,
-            })
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    // Anomaly detection
-    let mut anomalies = Vec::new();
-    
-    // Check for excessive descent rates
-    if let Some(vs_i) = vspeed_idx {
-        let (times, vspeeds) = data.get_parameter_data(vs_i, 0, None, 1);
-        for (i, &vspeed) in vspeeds.iter().enumerate() {
-            if vspeed < -2000.0 {
-                anomalies.push(Anomaly {
-                    timestamp: times[i],
-                    severity: "high".to_string(),
-                    description: "Excessive descent rate".to_string(),
-                    parameter: "Vertical Speed".to_string(),
-                    value: vspeed,
-                });
+                (a - b).abs() > float_tolerance
             }
         }
-    }
-    
-    // Check for excessive G-forces
-    if let Some(g_i) = g_force_idx {
-        let (times, g_forces) = data.get_parameter_data(g_i, 0, None, 1);
-        for (i, &g) in g_forces.iter().enumerate() {
-            if g > 2.5 || g < -1.0 {
-                let severity = if g > 3.0 || g < -1.5 { "high" } else { "medium" };
-                anomalies.push(Anomaly {
-                    timestamp: times[i],
-                    severity: severity.to_string(),
-                    description: "Excessive G-force".to_string(),
-                    parameter: "G Load".to_string(),
-                    value: g,
-                });
+        (DataValueJson::Double(a), DataValueJson::Double(b)) => {
+            if a.is_nan() || b.is_nan() {
+                a.is_nan() != b.is_nan()
+            } else {
+                (a - b).abs() > float_tolerance as f64
             }
         }
+        (DataValueJson::Int(a), DataValueJson::Int(b)) => a != b,
+        (DataValueJson::String(a), DataValueJson::String(b)) => a != b,
+        (DataValueJson::Missing, DataValueJson::Missing) => false,
+        _ => true,
     }
+}
+
+#[tauri::command]
+async fn export_csv(
+    filepath: String,
+    parameters: Vec<xdr::Parameter>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let output_path = state.validate_output(&filepath, &["csv"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    state.logger.log_info(&format!(
+        "Exporting {} parameters to CSV: {}",
+        parameters.len(),
+        sanitize_error_message(&filepath)
+    ));
+
+    let result = File::create(&output_path)
+        .and_then(|file| data.export_csv(&parameters, &mut BufWriter::new(file)));
+
+    result.map_err(|e| {
+        let error_msg = format!("Failed to export CSV: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
+}
+
+#[tauri::command]
+async fn export_parquet(
+    filepath: String,
+    parameters: Vec<xdr::Parameter>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let output_path = state.validate_output(&filepath, &["parquet"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    state.logger.log_info(&format!(
+        "Exporting {} parameters to Parquet: {}",
+        parameters.len(),
+        sanitize_error_message(&filepath)
+    ));
+
+    data.export_parquet(&parameters, &output_path).map_err(|e| {
+        let error_msg = format!("Failed to export Parquet: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
+}
+
+/// Write the header and dataref schema, without frame values, as JSON
+#[tauri::command]
+async fn export_schema(filepath: String, state: State<'_, AppState>) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let output_path = state.validate_output(&filepath, &["json"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    state.logger.log_info(&format!(
+        "Exporting schema to JSON: {}",
+        sanitize_error_message(&filepath)
+    ));
+
+    let result = File::create(&output_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &data.schema_json())?;
+        writer.flush()
+    });
+
+    result.map_err(|e| {
+        let error_msg = format!("Failed to export schema: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
+}
+
+#[tauri::command]
+async fn export_geojson(filepath: String, state: State<'_, AppState>) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
 
-    // Calculate average fuel flow
-    let average_fuel_flow = if let Some(ff_i) = fuel_flow_idx {
-        let (_, fuel_flows) = data.get_parameter_data(ff_i, 0, None, 1);
-        if !fuel_flows.is_empty() {
-            // Ensure the sum has an explicit numeric type
-            let fuel_flow_sum: f64 = fuel_flows.iter().copied().sum::<f64>();
-            let fuel_flow_count = fuel_flows.len();
-            Some(fuel_flow_sum / fuel_flow_count as f64)
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    let output_path = state.validate_output(&filepath, &["geojson", "json"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
 
-    let total_time = if !data.frames.is_empty() {
-        data.frames.last().unwrap().timestamp - data.frames.first().unwrap().timestamp
-    } else {
-        0.0
-    };
+    state.logger.log_info(&format!(
+        "Exporting flight path to GeoJSON: {}",
+        sanitize_error_message(&filepath)
+    ));
+
+    let result = File::create(&output_path)
+        .and_then(|file| data.export_geojson(&mut BufWriter::new(file)));
+
+    result.map_err(|e| {
+        let error_msg = format!("Failed to export GeoJSON: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
+}
+
+#[tauri::command]
+async fn export_gpx(filepath: String, state: State<'_, AppState>) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
+
+    let output_path = state.validate_output(&filepath, &["gpx"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
 
     state.logger.log_info(&format!(
-        "Flight analysis completed: {} phases, {} anomalies detected",
-        phases.len(),
-        anomalies.len()
+        "Exporting flight path to GPX: {}",
+        sanitize_error_message(&filepath)
     ));
 
-    Ok(FlightAnalysis {
-        phases,
-        total_flight_time: total_time,
-        max_altitude,
-        max_speed,
-        average_fuel_flow,
-        landing_g_force: landing_g,
-        max_climb_rate,
-        max_descent_rate,
-        approach_analysis,
-        anomalies,
+    let result = File::create(&output_path)
+        .and_then(|file| data.export_gpx(&mut BufWriter::new(file)));
+
+    result.map_err(|e| {
+        let error_msg = format!("Failed to export GPX: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
     })
 }
 
 #[tauri::command]
-async fn get_correlation(
-    request: GetCorrelationRequest,
+async fn export_report(
+    filepath: String,
+    format: Option<ReportFormat>,
+    thresholds: Option<AnalysisThresholds>,
     state: State<'_, AppState>,
-) -> Result<CorrelationResponse, String> {
-    let data_guard = state.xdr_data.lock().unwrap();
+) -> Result<(), String> {
+    let data_guard = state.files.lock().unwrap();
     let data = data_guard
-        .as_ref()
+        .current()
         .ok_or_else(|| "No file loaded".to_string())?;
 
-    let n = request.parameters.len();
-    let mut matrix = vec![vec![0.0; n]; n];
-    let names: Vec<String> = request.parameters.iter().map(|p| p.name.clone()).collect();
+    let output_path = state.validate_output(&filepath, &["md", "markdown"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
 
-    for i in 0..n {
-        for j in 0..n {
-            if i == j {
-                matrix[i][j] = 1.0;
-            } else {
-                let corr = data.calculate_correlation(
-                    request.parameters[i].index,
-                    request.parameters[i].array_index,
-                    request.parameters[j].index,
-                    request.parameters[j].array_index,
-                );
-                matrix[i][j] = corr;
-            }
-        }
-    }
+    let thresholds = thresholds.unwrap_or_default();
+    let analysis = run_flight_analysis(data, &thresholds)?;
+    let report = match format.unwrap_or_default() {
+        ReportFormat::Markdown => data.generate_report(&analysis),
+    };
 
-    Ok(CorrelationResponse { matrix, names })
+    state.logger.log_info(&format!(
+        "Exporting flight report: {}",
+        sanitize_error_message(&filepath)
+    ));
+
+    std::fs::write(&output_path, report).map_err(|e| {
+        let error_msg = format!("Failed to export report: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
 }
 
 #[tauri::command]
-async fn get_flight_path(state: State<'_, AppState>) -> Result<FlightPathResponse, String> {
-    let data_guard = state.xdr_data.lock().unwrap();
+async fn export_anomalies_csv(
+    filepath: String,
+    thresholds: Option<AnalysisThresholds>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let data_guard = state.files.lock().unwrap();
     let data = data_guard
-        .as_ref()
+        .current()
         .ok_or_else(|| "No file loaded".to_string())?;
 
-    match data.get_flight_path() {
-        Some((lats, lons, alts, times)) => Ok(FlightPathResponse {
-            latitudes: lats,
-            longitudes: lons,
-            altitudes: alts,
-            timestamps: times,
-        }),
-        None => Err("Position data not found".to_string()),
-    }
+    let output_path = state.validate_output(&filepath, &["csv"]).map_err(|e| {
+        let error_msg = format!("Export path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    let thresholds = thresholds.unwrap_or_default();
+    let analysis = run_flight_analysis(data, &thresholds)?;
+
+    state.logger.log_info(&format!(
+        "Exporting {} anomalies to CSV: {}",
+        analysis.anomalies.len(),
+        sanitize_error_message(&filepath)
+    ));
+
+    let result = File::create(&output_path)
+        .and_then(|file| data.export_anomalies_csv(&analysis.anomalies, &mut BufWriter::new(file)));
+
+    result.map(|_| analysis.anomalies.len()).map_err(|e| {
+        let error_msg = format!("Failed to export anomalies: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
+}
+
+/// Suggested view window around the airborne part of the flight
+#[derive(Debug, Serialize)]
+struct TrimRange {
+    start: f32,
+    end: f32,
 }
 
+/// "Zoom to flight": bounds of the airborne portion using the takeoff/landing
+/// AGL threshold, padded by `margin_secs` (default 30s)
 #[tauri::command]
-async fn get_table_data(
-    request: GetTableDataRequest,
+async fn get_auto_trim(
+    margin_secs: Option<f32>,
+    thresholds: Option<AnalysisThresholds>,
     state: State<'_, AppState>,
-) -> Result<TableDataResponse, String> {
-    let data_guard = state.xdr_data.lock().unwrap();
+) -> Result<TrimRange, String> {
+    let data_guard = state.files.lock().unwrap();
     let data = data_guard
-        .as_ref()
+        .current()
         .ok_or_else(|| "No file loaded".to_string())?;
 
-    let end = (request.start + request.count).min(data.frames.len());
-    let mut rows = Vec::new();
+    let thresholds = thresholds.unwrap_or_default();
+    let (start, end) = data.auto_trim_ground(
+        thresholds.takeoff_landing_agl,
+        margin_secs.unwrap_or(xdr::DEFAULT_TRIM_MARGIN_SECS),
+    );
 
-    for i in request.start..end {
-        let frame = &data.frames[i];
-        let mut values = Vec::new();
+    Ok(TrimRange { start, end })
+}
 
-        for value in &frame.values {
-            match value {
-                xdr::DataValue::Float(v) => values.push(DataValueJson::Float(*v)),
-                xdr::DataValue::Int(v) => values.push(DataValueJson::Int(*v)),
-                xdr::DataValue::String(v) => values.push(DataValueJson::String(v.clone())),
-                xdr::DataValue::FloatArray(arr) => {
-                    for v in arr {
-                        values.push(DataValueJson::Float(*v));
-                    }
-                }
-                xdr::DataValue::IntArray(arr) => {
-                    for v in arr {
-                        values.push(DataValueJson::Int(*v));
-                    }
-                }
-            }
-        }
+#[tauri::command]
+async fn trim_and_save(
+    start: f32,
+    end: f32,
+    filepath: String,
+    rebase: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let data_guard = state.files.lock().unwrap();
+    let data = data_guard
+        .current()
+        .ok_or_else(|| "No file loaded".to_string())?;
 
-        rows.push(TableRow {
-            index: i,
-            timestamp: frame.timestamp,
-            values,
-        });
-    }
+    let output_path = state.validate_output(&filepath, &["xdr"]).map_err(|e| {
+        let error_msg = format!("Save path validation failed: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
 
-    // Build headers
-    let mut headers = vec!["Index".to_string(), "Timestamp".to_string()];
-    for dr in &data.datarefs {
-        if dr.array_size > 0 {
-            for j in 0..dr.array_size {
-                headers.push(format!("{}[{}]", dr.name, j));
-            }
-        } else {
-            headers.push(dr.name.clone());
-        }
+    let mut trimmed = data.trim(start, end);
+    if trimmed.frames.is_empty() {
+        return Err("No frames in the selected time range".to_string());
+    }
+    if rebase.unwrap_or(false) {
+        trimmed.rebase_timestamps();
     }
 
-    Ok(TableDataResponse {
-        headers,
-        rows,
-        total: data.frames.len(),
-    })
+    state.logger.log_info(&format!(
+        "Saving trimmed recording ({}s-{}s, {} frames): {}",
+        start,
+        end,
+        trimmed.frames.len(),
+        sanitize_error_message(&filepath)
+    ));
+
+    trimmed.write(&output_path).map_err(|e| {
+        let error_msg = format!("Failed to save trimmed recording: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    Ok(trimmed.frames.len())
 }
 
 #[tauri::command]
 async fn get_log_path(state: State<'_, AppState>) -> Result<String, String> {
-    Ok(state.logger.get_log_path())
+    state
+        .logger
+        .get_log_path()
+        .ok_or_else(|| "Logs are not being written to a file".to_string())
+}
+
+#[tauri::command]
+async fn set_log_level(level: String, state: State<'_, AppState>) -> Result<(), String> {
+    let filter = level.trim().parse::<log::LevelFilter>().map_err(|_| {
+        let error_msg = format!("Invalid log level: {}", level);
+        state.logger.log_warning(&error_msg);
+        sanitize_error_message(&error_msg)
+    })?;
+
+    state.logger.set_level(filter);
+    state.logger.log_info(&format!("Log level set to {}", filter));
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_recent_logs(
+    lines: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let count = lines.unwrap_or(DEFAULT_RECENT_LINES);
+
+    state.logger.read_recent_lines(count).map_err(|e| {
+        let error_msg = format!("Failed to read log file: {}", e);
+        state.logger.log_error(&error_msg);
+        sanitize_error_message(&error_msg)
+    })
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Initialize logger - this is critical for debugging and security auditing
-    // We panic on logger initialization failure because:
-    // 1. Logging is essential for tracking security events and debugging issues
-    // 2. Without logging, we cannot audit file access or track potential security breaches
-    // 3. The application should not run in a degraded state without logging
-    let logger = AppLogger::new().unwrap_or_else(|e| {
-        eprintln!("FATAL: Failed to initialize logger: {}", e);
-        eprintln!("The application requires write access to the home directory for logging.");
-        eprintln!("Please ensure you have write permissions to: ~/.xblackbox/logs/");
-        panic!("Cannot initialize logging system: {}", e);
+    // Initialize logger - this is critical for debugging and security auditing.
+    // When the log directory isn't writable (sandboxes, containers without a
+    // HOME) entries go to stderr instead, so security events are still recorded.
+    let log_format_setting = std::env::var(LOG_FORMAT_ENV).ok();
+    let log_format = log_format_setting
+        .as_deref()
+        .and_then(LogFormat::from_name)
+        .unwrap_or_default();
+    let logger = AppLogger::new(log_format).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to open log file in ~/.xblackbox/logs/: {}", e);
+        eprintln!("Logging to stderr instead.");
+        AppLogger::with_writer(log_format, Box::new(std::io::stderr()))
     });
     
     logger.log_info("Initializing XBlackBox Tauri application");
+    if let Some(value) = log_format_setting.filter(|v| LogFormat::from_name(v).is_none()) {
+        logger.log_warning(&format!(
+            "Ignoring invalid {} value '{}', using text",
+            LOG_FORMAT_ENV, value
+        ));
+    }
+
+    let max_file_size = match std::env::var(MAX_FILE_SIZE_ENV) {
+        Ok(value) => value.trim().parse::<u64>().unwrap_or_else(|_| {
+            logger.log_warning(&format!(
+                "Ignoring invalid {} value '{}', using default",
+                MAX_FILE_SIZE_ENV, value
+            ));
+            DEFAULT_MAX_FILE_SIZE
+        }),
+        Err(_) => DEFAULT_MAX_FILE_SIZE,
+    };
+    logger.log_info(&format!("Maximum file size: {} bytes", max_file_size));
+
+    let follow_symlinks = match std::env::var(FOLLOW_SYMLINKS_ENV) {
+        Ok(value) => !matches!(value.trim().to_lowercase().as_str(), "0" | "false"),
+        Err(_) => true,
+    };
+    if !follow_symlinks {
+        logger.log_info("Symlinked file paths will be rejected");
+    }
+
+    let allowed_dirs: Vec<PathBuf> = std::env::var_os(ALLOWED_DIRS_ENV)
+        .map(|value| std::env::split_paths(&value).filter(|dir| !dir.as_os_str().is_empty()).collect())
+        .unwrap_or_default();
+    for dir in &allowed_dirs {
+        if dir.canonicalize().is_err() {
+            logger.log_warning(&format!("Allowed directory {} does not exist", dir.display()));
+        }
+    }
+    if !allowed_dirs.is_empty() {
+        logger.log_info(&format!("File access restricted to {} allowed directories", allowed_dirs.len()));
+    }
     
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .manage(AppState {
-            xdr_data: Mutex::new(None),
+            files: Mutex::new(LoadedFiles::default()),
             logger,
+            max_file_size,
+            display_name_overrides: Mutex::new(HashMap::new()),
+            nan_policy: Mutex::new(None),
+            follow_symlinks,
+            allowed_dirs,
         })
         .setup(|app| {
             if cfg!(debug_assertions) {
@@ -688,14 +3596,652 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             load_file,
+            verify_file,
+            merge_files,
+            unload_file,
+            select_file,
+            poll_file,
+            compare_parameter,
+            peek_file,
+            diff_files,
+            get_level_completeness,
+            get_parameter_groups,
+            set_display_names,
+            set_nan_policy,
             get_data,
+            get_data_by_name,
+            search_frames,
             get_statistics,
+            get_all_statistics,
+            get_flat_datarefs,
+            get_array_statistics,
+            get_rolling_statistics,
+            get_histogram,
+            get_threshold_integral,
+            get_crossings,
+            get_smoothed,
+            get_derivative,
+            get_rate_of_turn,
+            get_integral,
             analyze_flight,
+            analyze_flight_timeline,
+            get_analysis_thresholds,
             get_correlation,
+            correlate_against,
+            get_cross_correlation,
+            get_spectrum,
             get_flight_path,
+            get_flight_path_metrics,
+            get_track_deviation,
+            get_altitude_profile,
+            get_derived_groundspeed,
+            get_specific_energy,
+            get_wind_estimate,
+            get_vg_envelope,
+            get_speed_comparison,
             get_table_data,
+            get_frame_at_time,
+            get_value_at_time,
+            get_gaps,
+            get_timestamp_report,
+            get_table_deltas,
+            export_csv,
+            export_parquet,
+            export_schema,
+            export_geojson,
+            export_gpx,
+            export_report,
+            export_anomalies_csv,
+            get_auto_trim,
+            trim_and_save,
             get_log_path,
+            get_recent_logs,
+            set_log_level,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Recording of float datarefs, one `(timestamp, values)` row per frame
+    fn recording(names: &[&str], rows: &[(f32, &[f32])]) -> xdr::XDRData {
+        let mut data = xdr::XDRData::new();
+        for name in names {
+            data.datarefs.push(xdr::DatarefDef {
+                name: name.to_string(),
+                data_type: "float".to_string(),
+                array_size: 0,
+            });
+        }
+        for (timestamp, values) in rows {
+            data.frames.push(xdr::DataFrame {
+                timestamp: *timestamp,
+                values: values.iter().map(|v| xdr::DataValue::Float(*v)).collect(),
+            });
+        }
+        data
+    }
+
+    /// Recording of float datarefs sampled once a second, one column per dataref
+    fn sampled(names: &[&str], columns: &[&[f32]]) -> xdr::XDRData {
+        let len = columns.iter().map(|c| c.len()).min().unwrap_or(0);
+        let rows: Vec<(f32, Vec<f32>)> = (0..len)
+            .map(|i| (i as f32, columns.iter().map(|c| c[i]).collect()))
+            .collect();
+        let rows: Vec<(f32, &[f32])> = rows.iter().map(|(t, v)| (*t, v.as_slice())).collect();
+        recording(names, &rows)
+    }
+
+    #[test]
+    fn test_airspeed_rules_use_knots() {
+        // 100 m/s is about 194 kt, 40 m/s about 78 kt
+        let data = recording(
+            &["sim/flightmodel/position/true_airspeed"],
+            &[(0.0, &[90.0]), (1.0, &[100.0]), (2.0, &[40.0]), (3.0, &[40.0])],
+        );
+
+        let overspeed = OverspeedRule {
+            airspeed_idx: 0,
+            overspeed_kias: 180.0,
+        };
+        let anomalies = overspeed.evaluate(&data);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].timestamp, 1.0);
+        assert!((anomalies[0].value - 194.38).abs() < 0.01);
+
+        let low = LowAirspeedRule {
+            airspeed_idx: 0,
+            low_airspeed_kias: 80.0,
+            min_secs: 1.0,
+            flight_windows: vec![(0.0, 10.0)],
+        };
+        let anomalies = low.evaluate(&data);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].timestamp, 2.0);
+    }
+
+    /// (timestamp, severity, value) of each anomaly
+    fn flagged(anomalies: &[Anomaly]) -> Vec<(f32, &str, f64)> {
+        anomalies.iter().map(|a| (a.timestamp, a.severity.as_str(), a.value)).collect()
+    }
+
+    #[test]
+    fn test_bank_angle_rule() {
+        let data = sampled(&["sim/flightmodel/position/phi"], &[&[10.0, -65.0, 80.0, -60.0, -76.0]]);
+        let rule = BankAngleRule {
+            bank_idx: 0,
+            warning_deg: 60.0,
+            critical_deg: 75.0,
+        };
+
+        // Either wing down counts; exactly at the warning limit does not
+        assert_eq!(
+            flagged(&rule.evaluate(&data)),
+            [(1.0, "medium", -65.0), (2.0, "high", 80.0), (4.0, "high", -76.0)]
+        );
+    }
+
+    #[test]
+    fn test_angle_of_attack_rule() {
+        let data = sampled(&["sim/flightmodel/position/alpha"], &[&[16.0, 5.0, 16.0, 19.0, 25.0]]);
+        let rule = AngleOfAttackRule {
+            aoa_idx: 0,
+            critical_deg: 15.0,
+            stall_deg: 18.0,
+            flight_windows: vec![(1.0, 4.0)],
+        };
+
+        // Beyond the stall angle escalates; on the ground nothing is flagged
+        assert_eq!(flagged(&rule.evaluate(&data)), [(2.0, "medium", 16.0), (3.0, "high", 19.0)]);
+    }
+
+    #[test]
+    fn test_turn_rate_rule() {
+        // 20 deg/s through north, then easing to a stop
+        let data = sampled(&["sim/flightmodel/position/psi"], &[&[350.0, 10.0, 30.0, 50.0, 50.0]]);
+        let rule = TurnRateRule {
+            max_turn_rate_dps: 15.0,
+            flight_windows: vec![(0.0, 2.0)],
+        };
+
+        // Only airborne samples are flagged, and never above medium severity
+        assert_eq!(flagged(&rule.evaluate(&data)), [(0.0, "medium", 20.0), (1.0, "medium", 20.0)]);
+
+        let gentle = TurnRateRule {
+            max_turn_rate_dps: 20.0,
+            flight_windows: vec![(0.0, 5.0)],
+        };
+        assert!(gentle.evaluate(&data).is_empty());
+    }
+
+    #[test]
+    fn test_total_fuel_flow() {
+        let mut data = xdr::XDRData::new();
+        data.datarefs.push(xdr::DatarefDef {
+            name: "sim/flightmodel/engine/ENGN_FF_".to_string(),
+            data_type: "float".to_string(),
+            array_size: 2,
+        });
+        for (timestamp, flows) in [(0.0, [0.25, 0.5]), (1.0, [0.5, 0.5])] {
+            data.frames.push(xdr::DataFrame {
+                timestamp,
+                values: vec![xdr::DataValue::FloatArray(flows.to_vec())],
+            });
+        }
+
+        let (times, totals) = total_fuel_flow(&data, 0, None);
+        assert_eq!(times, vec![0.0, 1.0]);
+        assert_eq!(totals, vec![0.75, 1.0]);
+        assert_eq!(total_fuel_flow(&data, 0, Some((0.5, 2.0))).1, vec![1.0]);
+    }
+
+    #[test]
+    fn test_classify_landing() {
+        let thresholds = AnalysisThresholds::default();
+        let classify = |g, vs| classify_landing(g, vs, &thresholds);
+
+        assert_eq!(classify(None, None), None);
+        assert_eq!(classify(Some(1.2), Some(-150.0)).as_deref(), Some("smooth"));
+        assert_eq!(classify(Some(1.8), Some(-150.0)).as_deref(), Some("firm"));
+        assert_eq!(classify(Some(1.2), Some(-450.0)).as_deref(), Some("firm"));
+        assert_eq!(classify(Some(2.3), None).as_deref(), Some("hard"));
+        assert_eq!(classify(None, Some(-700.0)).as_deref(), Some("hard"));
+        // Climbing at touchdown (a bounce) is no sink rate at all
+        assert_eq!(classify(None, Some(200.0)).as_deref(), Some("smooth"));
+    }
+
+    #[test]
+    fn test_flight_analysis_y_agl() {
+        // y_agl is recorded in meters: 5 m (16 ft) is already past the 10 ft threshold
+        let data = sampled(
+            &["sim/flightmodel/position/y_agl"],
+            &[&[0.0, 0.0, 5.0, 20.0, 100.0, 100.0, 20.0, 5.0, 0.0, 0.0]],
+        );
+
+        let analysis = run_flight_analysis(&data, &AnalysisThresholds::default()).unwrap();
+        let names: Vec<&str> = analysis.phases.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Takeoff", "Landing"]);
+        assert_eq!((analysis.phases[0].start_time, analysis.phases[0].end_time), (2.0, 8.0));
+        assert_eq!(analysis.phases[1].start_time, 8.0);
+        assert!((analysis.max_altitude - 328.08).abs() < 0.01);
+    }
+
+    /// (name, start, end) of every phase, for comparing whole segmentations
+    fn phase_spans(analysis: &FlightAnalysis) -> Vec<(&str, f32, f32)> {
+        analysis
+            .phases
+            .iter()
+            .map(|p| (p.name.as_str(), p.start_time, p.end_time))
+            .collect()
+    }
+
+    #[test]
+    fn test_flight_analysis_touch_and_go() {
+        // Two circuits: 30 m (98 ft) clears the +50 ft hysteresis both times
+        let data = sampled(
+            &["sim/flightmodel/position/y_agl", "sim/flightmodel/forces/g_nrml"],
+            &[
+                &[0.0, 0.0, 30.0, 30.0, 0.0, 0.0, 30.0, 30.0, 0.0, 0.0],
+                &[1.0, 1.0, 1.0, 1.0, 1.75, 1.0, 1.0, 1.0, 1.25, 1.0],
+            ],
+        );
+
+        let analysis = run_flight_analysis(&data, &AnalysisThresholds::default()).unwrap();
+        assert_eq!(
+            phase_spans(&analysis),
+            [
+                ("Takeoff", 2.0, 4.0),
+                ("Landing", 4.0, 6.0),
+                ("Takeoff", 6.0, 8.0),
+                ("Landing", 8.0, 9.0),
+            ]
+        );
+        // Each landing keeps its own touchdown G; the summary reports the last
+        assert_eq!(analysis.phases[1].landing_g_force, Some(1.75));
+        assert_eq!(analysis.phases[3].landing_g_force, Some(1.25));
+        assert_eq!(analysis.landing_g_force, Some(1.25));
+        assert_eq!(analysis.landing_quality.as_deref(), Some("smooth"));
+    }
+
+    #[test]
+    fn test_flight_analysis_runway_noise() {
+        // 4 m (13 ft) blips pass the 10 ft threshold but never arm a landing
+        let data = sampled(
+            &["sim/flightmodel/position/y_agl"],
+            &[&[0.0, 4.0, 0.0, 4.0, 0.0, 0.0, 30.0, 30.0, 0.0, 0.0]],
+        );
+
+        let analysis = run_flight_analysis(&data, &AnalysisThresholds::default()).unwrap();
+        assert_eq!(phase_spans(&analysis), [("Takeoff", 6.0, 8.0), ("Landing", 8.0, 9.0)]);
+
+        let noise_only = sampled(&["sim/flightmodel/position/y_agl"], &[&[0.0, 4.0, 0.0, 4.0, 0.0]]);
+        let analysis = run_flight_analysis(&noise_only, &AnalysisThresholds::default()).unwrap();
+        assert!(analysis.phases.is_empty());
+        assert_eq!(analysis.landing_g_force, None);
+    }
+
+    #[test]
+    fn test_flight_analysis_go_around() {
+        // Dips from 100 m (328 ft) to 40 m (131 ft), below the 200 ft go-around height
+        let agl: &[f32] = &[0.0, 100.0, 100.0, 40.0, 40.0, 100.0, 100.0, 0.0, 0.0];
+        let go_arounds = |data: &xdr::XDRData| {
+            let analysis = run_flight_analysis(data, &AnalysisThresholds::default()).unwrap();
+            analysis
+                .phases
+                .iter()
+                .filter(|p| p.name == "Go-Around")
+                .map(|p| (p.start_time, p.end_time))
+                .collect::<Vec<_>>()
+        };
+
+        // Without vertical speed the climb back out is enough
+        let data = sampled(&["sim/flightmodel/position/y_agl"], &[agl]);
+        assert_eq!(go_arounds(&data), [(3.0, 5.0)]);
+        let analysis = run_flight_analysis(&data, &AnalysisThresholds::default()).unwrap();
+        assert_eq!(
+            phase_spans(&analysis),
+            [("Takeoff", 1.0, 7.0), ("Go-Around", 3.0, 5.0), ("Landing", 7.0, 8.0)]
+        );
+
+        // With vertical speed, the descent must reverse into a climb
+        let names = ["sim/flightmodel/position/y_agl", "sim/flightmodel/position/vh_ind_fpm"];
+        let reversed = sampled(&names, &[agl, &[0.0, 500.0, 0.0, -500.0, -500.0, 500.0, 0.0, -500.0, 0.0]]);
+        assert_eq!(go_arounds(&reversed), [(3.0, 5.0)]);
+        let level = sampled(&names, &[agl, &[0.0, 500.0, 0.0, -500.0, -500.0, 0.0, 0.0, -500.0, 0.0]]);
+        assert!(go_arounds(&level).is_empty());
+    }
+
+    #[test]
+    fn test_align_to_timeline() {
+        let aligned = align_to_timeline(&[1.0, 2.0, 4.0], &[0.0, 1.0, 3.0, 4.0], &[10.0, 11.0, 13.0, 14.0]);
+        assert_eq!(aligned, vec![Some(11.0), None, Some(14.0)]);
+    }
+
+    #[test]
+    fn test_speed_comparison() {
+        // The first fix is uninitialized GPS, so derived speeds start at 2 s
+        let data = sampled(
+            &[
+                "sim/flightmodel/position/latitude",
+                "sim/flightmodel/position/longitude",
+                "sim/flightmodel/position/groundspeed",
+                "sim/flightmodel/position/true_airspeed",
+            ],
+            &[&[0.0, 47.0, 47.0, 47.0], &[0.0, 8.0, 8.001, 8.002], &[0.0, 70.0, 75.0, 76.0], &[0.0, 80.0, 85.0, 86.0]],
+        );
+
+        let comparison = speed_comparison(&data).unwrap();
+        assert_eq!(comparison.timestamps, vec![2.0, 3.0]);
+        assert!((comparison.derived_groundspeed[0] - 75.8).abs() < 0.5);
+        assert_eq!(comparison.recorded_groundspeed, Some(vec![Some(75.0), Some(76.0)]));
+        assert_eq!(comparison.true_airspeed, Some(vec![Some(85.0), Some(86.0)]));
+
+        let no_position = sampled(&["sim/flightmodel/position/groundspeed"], &[&[70.0, 75.0]]);
+        assert_eq!(speed_comparison(&no_position).err().as_deref(), Some("Position data not found"));
+    }
+
+    #[test]
+    fn test_table_deltas() {
+        let mut data = xdr::XDRData::new();
+        for (name, data_type) in [("sim/test/float", "float"), ("sim/test/int", "int"), ("sim/test/double", "double")] {
+            data.datarefs.push(xdr::DatarefDef {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+                array_size: 0,
+            });
+        }
+        for (timestamp, float, int, double) in [(0.0, 1.0, 1, 1.0), (1.0, 1.0000001, 2, 1.0), (2.0, 1.5, 2, 1.0000001)] {
+            data.frames.push(xdr::DataFrame {
+                timestamp,
+                values: vec![xdr::DataValue::Float(float), xdr::DataValue::Int(int), xdr::DataValue::Double(double)],
+            });
+        }
+        let deltas = |start, count, float_tolerance| {
+            let response = table_deltas(&data, &GetTableDeltasRequest { start, count, float_tolerance });
+            let columns: Vec<Vec<usize>> = response.rows.iter().map(|r| r.columns.clone()).collect();
+            (columns, response.total)
+        };
+
+        // The page's first row is complete even though nothing changed from
+        // frame 0; float and double drift within the tolerance is not a change
+        assert_eq!(deltas(1, 10, 1e-6), (vec![vec![0, 1, 2], vec![0]], 3));
+
+        // Ints compare exactly however large the float tolerance is
+        assert_eq!(deltas(0, 10, 1.0), (vec![vec![0, 1, 2], vec![1], vec![]], 3));
+        assert_eq!(deltas(5, 10, 1e-6), (vec![], 3));
+    }
+
+    fn phase(name: &str, start_time: f32, end_time: f32) -> FlightPhase {
+        FlightPhase {
+            name: name.to_string(),
+            start_time,
+            end_time,
+            duration: end_time - start_time,
+            average_altitude: None,
+            average_speed: None,
+            landing_g_force: None,
+            fuel_used: None,
+            average_fuel_flow: None,
+        }
+    }
+
+    #[test]
+    fn test_ground_windows() {
+        let phases = [
+            phase("Takeoff", 100.0, 130.0),
+            phase("Cruise", 130.0, 900.0),
+            phase("Landing", 900.0, 950.0),
+            phase("Takeoff", 1200.0, 1230.0),
+            phase("Landing", 1800.0, 1850.0),
+        ];
+        assert_eq!(
+            ground_windows(&phases, 0.0, 2000.0),
+            vec![
+                (0.0, 100.0, false, true),
+                (950.0, 1200.0, true, true),
+                (1850.0, 2000.0, true, false),
+            ]
+        );
+        // Never took off: the whole recording is one ground window
+        assert_eq!(ground_windows(&[], 0.0, 60.0), vec![(0.0, 60.0, false, false)]);
+    }
+
+    #[test]
+    fn test_segment_vertical_phases() {
+        // A centered 2 s window averages each sample with its neighbours
+        let smoothed = moving_average(&[0.0, 1.0, 2.0, 3.0, 4.0], &[0.0, 0.0, 9.0, 0.0, 0.0], 2.0);
+        assert_eq!(smoothed, vec![0.0, 3.0, 3.0, 3.0, 0.0]);
+
+        // Climb, cruise with a 3 s bump shorter than the dwell time, then descend
+        let times: Vec<f32> = (0..120).map(|t| t as f32).collect();
+        let vspeeds: Vec<f64> = times
+            .iter()
+            .map(|&t| match t {
+                t if t < 40.0 => 1000.0,
+                t if (72.0..75.0).contains(&t) => 1000.0,
+                t if t < 80.0 => 0.0,
+                _ => -1000.0,
+            })
+            .collect();
+        assert_eq!(
+            segment_vertical_phases(&times, &vspeeds, (0.0, 120.0), 300.0, 30.0),
+            vec![("Climb", 0.0, 40.0), ("Cruise", 40.0, 80.0), ("Descent", 80.0, 120.0)]
+        );
+
+        // A change that doesn't last the dwell time never becomes a phase
+        assert_eq!(
+            segment_vertical_phases(&times[..60], &vspeeds[..60], (0.0, 60.0), 300.0, 30.0),
+            vec![("Climb", 0.0, 60.0)]
+        );
+    }
+
+    #[test]
+    fn test_flight_analysis_speeds_in_knots() {
+        // Groundspeed is recorded in m/s; 10 m/s is 19.44 kt
+        let data = sampled(
+            &[
+                "sim/flightmodel/position/y_agl",
+                "sim/flightmodel/position/groundspeed",
+                "sim/flightmodel/position/vh_ind_fpm",
+            ],
+            &[
+                &[0.0, 0.0, 30.0, 30.0, 30.0, 0.0, 0.0],
+                &[0.0, 10.0, 10.0, 10.0, 10.0, 10.0, 0.0],
+                &[0.0, 0.0, 500.0, 0.0, -500.0, -200.0, 0.0],
+            ],
+        );
+
+        let analysis = run_flight_analysis(&data, &AnalysisThresholds::default()).unwrap();
+        assert!((analysis.max_speed - 19.438).abs() < 0.001);
+        let takeoff = analysis.phases.iter().find(|p| p.name == "Takeoff").unwrap();
+        assert!((takeoff.average_speed.unwrap() - 19.438).abs() < 0.001);
+        let approach = analysis.approach_analysis.unwrap();
+        assert!((approach.touchdown_speed - 19.438).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_segment_taxi_phases() {
+        let limits = (5.0, 40.0);
+        // Stationary, taxi from 10s to 40s, then the takeoff roll
+        let times: Vec<f32> = (0..=60).map(|t| t as f32).collect();
+        let speeds: Vec<f64> = times
+            .iter()
+            .map(|&t| if t < 10.0 { 0.0 } else if t <= 40.0 { 15.0 } else { 60.0 })
+            .collect();
+        assert_eq!(
+            segment_taxi_phases(&times, &speeds, (0.0, 60.0), (false, true), limits),
+            vec![("Taxi-Out", 10.0, 40.0)]
+        );
+
+        // After landing only, the same movement is a taxi-in
+        assert_eq!(
+            segment_taxi_phases(&times, &speeds, (0.0, 60.0), (true, false), limits),
+            vec![("Taxi-In", 10.0, 40.0)]
+        );
+
+        // Full-stop turnaround: taxi in, park from 31s to 79s, taxi out again.
+        // The short hold at 15-19s is not the split point.
+        let times: Vec<f32> = (0..=120).map(|t| t as f32).collect();
+        let speeds: Vec<f64> = times
+            .iter()
+            .map(|&t| {
+                let moving = (5.0..15.0).contains(&t) || (20.0..=30.0).contains(&t) || (80.0..=100.0).contains(&t);
+                if moving { 12.0 } else { 0.0 }
+            })
+            .collect();
+        assert_eq!(
+            segment_taxi_phases(&times, &speeds, (0.0, 120.0), (true, true), limits),
+            vec![("Taxi-In", 5.0, 30.0), ("Taxi-Out", 80.0, 100.0)]
+        );
+
+        // Too little movement for a taxi phase
+        assert!(segment_taxi_phases(&times, &speeds, (40.0, 60.0), (true, true), limits).is_empty());
+    }
+
+    fn sample(t: f32, severity: &str, parameter: &str, value: f64) -> Anomaly {
+        Anomaly {
+            timestamp: t,
+            end_timestamp: t,
+            frame_index: 0,
+            severity: severity.to_string(),
+            description: format!("{} limit", parameter),
+            parameter: parameter.to_string(),
+            value,
+            peak_value: value,
+        }
+    }
+
+    #[test]
+    fn test_build_timeline() {
+        let mut first_landing = phase("Landing", 10.0, 12.0);
+        first_landing.landing_g_force = Some(1.5);
+        let analysis = FlightAnalysis {
+            // Deliberately out of order; the timeline sorts by time
+            phases: vec![
+                phase("Takeoff", 12.0, 20.0),
+                phase("Takeoff", 0.0, 10.0),
+                first_landing,
+                phase("Landing", 20.0, 25.0),
+                phase("Go-Around", 15.0, 15.0),
+            ],
+            total_flight_time: 25.0,
+            max_altitude: 1000.0,
+            max_speed: 120.0,
+            average_fuel_flow: None,
+            landing_g_force: None,
+            landing_quality: Some("smooth".to_string()),
+            max_climb_rate: None,
+            max_descent_rate: None,
+            approach_analysis: None,
+            anomalies: vec![sample(10.0, "medium", "G-Force", 2.6), sample(5.0, "high", "Bank Angle", 80.0)],
+        };
+
+        let events: Vec<String> = build_timeline(analysis)
+            .iter()
+            .map(|event| match event {
+                TimelineEvent::PhaseStart { timestamp, phase } => format!("{} start {}", timestamp, phase),
+                TimelineEvent::PhaseEnd { timestamp, phase } => format!("{} end {}", timestamp, phase),
+                TimelineEvent::Landing { timestamp, quality, .. } => format!("{} touchdown {:?}", timestamp, quality),
+                TimelineEvent::Anomaly(a) => format!("{} anomaly {}", a.timestamp, a.parameter),
+            })
+            .collect();
+        assert_eq!(
+            events,
+            [
+                "0 start Takeoff",
+                "5 anomaly Bank Angle",
+                // A phase ends before the next starts; the touchdown and anomaly follow
+                "10 end Takeoff",
+                "10 start Landing",
+                "10 touchdown None",
+                "10 anomaly G-Force",
+                "12 end Landing",
+                "12 start Takeoff",
+                // A zero-length phase still starts before it ends
+                "15 start Go-Around",
+                "15 end Go-Around",
+                "20 end Takeoff",
+                "20 start Landing",
+                "20 touchdown Some(\"smooth\")",
+                "25 end Landing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_anomalies() {
+        let anomalies = vec![
+            sample(0.0, "medium", "G-Force", 2.6),
+            sample(0.5, "high", "G-Force", 3.2),
+            sample(1.0, "medium", "G-Force", 2.8),
+            // Another parameter at the same time stays separate
+            sample(0.5, "high", "Bank Angle", -50.0),
+            sample(0.75, "high", "Bank Angle", -62.0),
+            // 1.5s after the last G sample: a new event
+            sample(2.5, "medium", "G-Force", 2.7),
+        ];
+
+        let events = coalesce_anomalies(anomalies, 1.0);
+        assert_eq!(events.len(), 3);
+
+        let g = &events[0];
+        assert_eq!((g.parameter.as_str(), g.timestamp, g.end_timestamp), ("G-Force", 0.0, 1.0));
+        // First sample's value, worst severity, largest peak
+        assert_eq!(g.value, 2.6);
+        assert_eq!(g.severity, "high");
+        assert_eq!(g.peak_value, 3.2);
+
+        let bank = &events[1];
+        assert_eq!((bank.parameter.as_str(), bank.timestamp, bank.end_timestamp), ("Bank Angle", 0.5, 0.75));
+        // Peak by magnitude, keeping the sign
+        assert_eq!(bank.peak_value, -62.0);
+
+        let later = &events[2];
+        assert_eq!((later.timestamp, later.end_timestamp), (2.5, 2.5));
+        assert_eq!(later.severity, "medium");
+    }
+
+    #[test]
+    fn test_loaded_files() {
+        let mut files = LoadedFiles::default();
+        assert!(files.current().is_none());
+
+        let (first, evicted) = files.insert(xdr::XDRData::new());
+        assert_eq!(evicted, None);
+        let (second, _) = files.insert(xdr::XDRData::new());
+        assert_ne!(first, second);
+        assert_eq!(files.current.as_deref(), Some(second.as_str()));
+
+        assert!(files.select(&first));
+        assert_eq!(files.current.as_deref(), Some(first.as_str()));
+        assert!(!files.select("file-99"));
+        assert_eq!(files.current.as_deref(), Some(first.as_str()));
+
+        // Unloading the current file falls back to the newest remaining one
+        assert!(files.remove(&first).is_some());
+        assert!(files.remove(&first).is_none());
+        assert!(files.get(&first).is_none());
+        assert_eq!(files.current.as_deref(), Some(second.as_str()));
+        assert!(files.remove(&second).is_some());
+        assert!(files.current().is_none());
+    }
+
+    #[test]
+    fn test_loaded_files_cap() {
+        let mut files = LoadedFiles::default();
+        let ids: Vec<String> = (0..MAX_LOADED_FILES).map(|_| files.insert(xdr::XDRData::new()).0).collect();
+        assert_eq!(files.files.len(), MAX_LOADED_FILES);
+
+        // One more evicts the oldest and reports it
+        let (newest, evicted) = files.insert(xdr::XDRData::new());
+        assert_eq!(evicted.as_deref(), Some(ids[0].as_str()));
+        assert_eq!(files.files.len(), MAX_LOADED_FILES);
+        assert!(files.get(&ids[0]).is_none());
+        assert!(!files.select(&ids[0]));
+        assert!(files.get(&ids[1]).is_some());
+        assert_eq!(files.current.as_deref(), Some(newest.as_str()));
+    }
+}
@@ -1,24 +1,108 @@
 use chrono::Local;
+use log::{Level, LevelFilter};
+use serde_json::json;
 use std::fs::{self, File, OpenOptions};
-use std::io::Write;
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+/// Default number of lines returned by `read_recent_lines` callers
+pub const DEFAULT_RECENT_LINES: usize = 100;
+
+/// Chunk size used when reading the log file backwards
+const TAIL_CHUNK_SIZE: u64 = 8 * 1024;
+
+/// Default log level: verbose in debug builds, quieter in release
+pub const DEFAULT_LOG_LEVEL: LevelFilter = if cfg!(debug_assertions) {
+    LevelFilter::Debug
+} else {
+    LevelFilter::Info
+};
+
+/// Default budget for all log files together
+pub const DEFAULT_MAX_TOTAL_LOG_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Default number of log files kept
+pub const DEFAULT_MAX_LOG_FILES: usize = 30;
+
+/// Limits applied to the log directory when rotating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Most log files kept, newest first
+    pub max_files: usize,
+    /// Oldest files are deleted once all files together exceed this size
+    pub max_total_bytes: u64,
+    /// Size at which the current file rolls over to `xblackbox_YYYYMMDD.N.log`
+    pub max_file_bytes: Option<u64>,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        RotationPolicy {
+            max_files: DEFAULT_MAX_LOG_FILES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_LOG_BYTES,
+            max_file_bytes: None,
+        }
+    }
+}
+
+/// Format of each log file entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `[timestamp] [level] message`
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp`, `level` and `message` fields
+    Json,
+}
+
+impl LogFormat {
+    /// Parse a format name (`text` or `json`, case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Destination of log entries
+enum LogSink {
+    /// Daily log file, `None` while it is being rolled over
+    File(Option<File>),
+    /// Caller-supplied stream (stderr, a pipe, an in-memory buffer)
+    Writer(Box<dyn Write + Send>),
+}
+
 /// Logger for XBlackBox application
-/// Stores logs in the user's home directory under .xblackbox/logs/
+/// Stores logs in the user's home directory under .xblackbox/logs/,
+/// or in any writer passed to `with_writer`
 pub struct AppLogger {
-    log_file: Mutex<Option<File>>,
-    log_path: PathBuf,
+    sink: Mutex<LogSink>,
+    /// Current log file; `None` when logging to a writer
+    log_path: Option<PathBuf>,
+    level: Mutex<LevelFilter>,
+    format: LogFormat,
+    rotation: RotationPolicy,
 }
 
 impl AppLogger {
     /// Create a new logger instance
     /// Logs are stored in ~/.xblackbox/logs/xblackbox_YYYYMMDD.log
-    pub fn new() -> Result<Self, std::io::Error> {
-        let log_dir = Self::get_log_directory()?;
-        
+    pub fn new(format: LogFormat) -> Result<Self, std::io::Error> {
+        Self::with_rotation(format, RotationPolicy::default())
+    }
+    
+    /// Create a logger with custom rotation limits
+    pub fn with_rotation(format: LogFormat, rotation: RotationPolicy) -> Result<Self, std::io::Error> {
+        Self::in_directory(&Self::get_log_directory()?, format, rotation)
+    }
+    
+    /// Create a logger writing daily files to `log_dir`
+    fn in_directory(log_dir: &Path, format: LogFormat, rotation: RotationPolicy) -> Result<Self, std::io::Error> {
         // Create log directory if it doesn't exist
-        fs::create_dir_all(&log_dir)?;
+        fs::create_dir_all(log_dir)?;
         
         // Create log file with current date
         let log_filename = format!("xblackbox_{}.log", Local::now().format("%Y%m%d"));
@@ -30,8 +114,11 @@ impl AppLogger {
             .open(&log_path)?;
         
         let logger = AppLogger {
-            log_file: Mutex::new(Some(log_file)),
-            log_path: log_path.clone(),
+            sink: Mutex::new(LogSink::File(Some(log_file))),
+            log_path: Some(log_path.clone()),
+            level: Mutex::new(DEFAULT_LOG_LEVEL),
+            format,
+            rotation,
         };
         
         // Log startup message
@@ -39,11 +126,23 @@ impl AppLogger {
         logger.log_info(&format!("Log file: {}", log_path.display()));
         
         // Perform log rotation
-        Self::rotate_logs(&log_dir)?;
+        Self::rotate_logs(log_dir, &rotation, &log_path)?;
         
         Ok(logger)
     }
     
+    /// Create a logger writing to `writer` instead of the log directory.
+    /// Entries are not rotated and can't be read back with `read_recent_lines`.
+    pub fn with_writer(format: LogFormat, writer: Box<dyn Write + Send>) -> Self {
+        AppLogger {
+            sink: Mutex::new(LogSink::Writer(writer)),
+            log_path: None,
+            level: Mutex::new(DEFAULT_LOG_LEVEL),
+            format,
+            rotation: RotationPolicy::default(),
+        }
+    }
+    
     /// Get the log directory path
     fn get_log_directory() -> Result<PathBuf, std::io::Error> {
         // Try multiple methods to get home directory for better cross-platform support
@@ -58,17 +157,18 @@ impl AppLogger {
         Ok(home_dir.join(".xblackbox").join("logs"))
     }
     
-    /// Rotate logs - keep only last 30 days of logs
-    fn rotate_logs(log_dir: &Path) -> Result<(), std::io::Error> {
+    /// Rotate logs - keep at most `max_files` files and `max_total_bytes` in
+    /// total, deleting the oldest first. `current` (the open log) is never deleted.
+    fn rotate_logs(log_dir: &Path, policy: &RotationPolicy, current: &Path) -> Result<(), std::io::Error> {
         let entries = fs::read_dir(log_dir)?;
-        let mut log_files: Vec<(PathBuf, std::time::SystemTime)> = Vec::new();
+        let mut log_files: Vec<(PathBuf, std::time::SystemTime, u64)> = Vec::new();
         
         for entry in entries.flatten() {
             let path = entry.path();
             if path.is_file() && path.extension().map_or(false, |ext| ext == "log") {
                 if let Ok(metadata) = entry.metadata() {
                     if let Ok(modified) = metadata.modified() {
-                        log_files.push((path, modified));
+                        log_files.push((path, modified, metadata.len()));
                     }
                 }
             }
@@ -77,61 +177,193 @@ impl AppLogger {
         // Sort by modification time (newest first)
         log_files.sort_by(|a, b| b.1.cmp(&a.1));
         
-        // Keep only the 30 most recent log files
-        for (path, _) in log_files.iter().skip(30) {
-            let _ = fs::remove_file(path);
+        // Keep the most recent files that fit both the count and size budgets
+        let mut total_bytes = 0u64;
+        for (i, (path, _, size)) in log_files.iter().enumerate() {
+            total_bytes = total_bytes.saturating_add(*size);
+            if path == current {
+                continue;
+            }
+            if i >= policy.max_files || total_bytes > policy.max_total_bytes {
+                let _ = fs::remove_file(path);
+            }
         }
         
         Ok(())
     }
     
+    /// Rename a full log file to the first free `<stem>.N.log` next to it
+    fn roll_file(path: &Path) -> Result<PathBuf, std::io::Error> {
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        
+        let mut n = 1;
+        loop {
+            let rolled = dir.join(format!("{}.{}.log", stem, n));
+            if !rolled.exists() {
+                fs::rename(path, &rolled)?;
+                return Ok(rolled);
+            }
+            n += 1;
+        }
+    }
+    
+    /// Roll the current file over once it exceeds `max_file_bytes`
+    fn roll_if_full(&self, file_guard: &mut Option<File>) {
+        let (max_file_bytes, log_path) = match (self.rotation.max_file_bytes, &self.log_path) {
+            (Some(max), Some(path)) => (max, path),
+            _ => return,
+        };
+        let size = file_guard.as_ref().and_then(|f| f.metadata().ok()).map_or(0, |m| m.len());
+        if size < max_file_bytes {
+            return;
+        }
+        
+        // Close the file first; open files can't be renamed on Windows
+        *file_guard = None;
+        if let Err(e) = Self::roll_file(log_path) {
+            eprintln!("Warning: Failed to roll log file: {}", e);
+        }
+        match OpenOptions::new().create(true).append(true).open(log_path) {
+            Ok(file) => *file_guard = Some(file),
+            Err(e) => eprintln!("Warning: Failed to reopen log file: {}", e),
+        }
+        if let Some(log_dir) = log_path.parent() {
+            let _ = Self::rotate_logs(log_dir, &self.rotation, log_path);
+        }
+    }
+    
+    /// Set the minimum level written to the log file
+    pub fn set_level(&self, level: LevelFilter) {
+        if let Ok(mut guard) = self.level.lock() {
+            *guard = level;
+        }
+    }
+    
+    /// Get the current minimum log level
+    pub fn level(&self) -> LevelFilter {
+        self.level.lock().map(|guard| *guard).unwrap_or(DEFAULT_LOG_LEVEL)
+    }
+    
     /// Write a log entry with the given level
+    /// Entries below the configured level are skipped.
     /// Note: This holds the mutex during I/O. For high-throughput scenarios,
     /// consider using async I/O or a separate logging thread in future versions.
-    fn write_log(&self, level: &str, message: &str) {
-        let timestamp = Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
-        let log_entry = format!("[{}] [{}] {}\n", timestamp, level, message);
+    fn write_log(&self, level: Level, message: &str) {
+        if level > self.level() {
+            return;
+        }
+        
+        let log_entry = self.format_entry(level, message);
         
-        if let Ok(mut file_guard) = self.log_file.lock() {
-            if let Some(ref mut file) = *file_guard {
+        if let Ok(mut sink_guard) = self.sink.lock() {
+            let writer: Option<&mut dyn Write> = match &mut *sink_guard {
+                LogSink::File(file) => file.as_mut().map(|f| f as &mut dyn Write),
+                LogSink::Writer(writer) => Some(writer.as_mut()),
+            };
+            if let Some(writer) = writer {
                 // Log write failures to stderr as we can't log them to the file
-                if let Err(e) = file.write_all(log_entry.as_bytes()) {
+                if let Err(e) = writer.write_all(log_entry.as_bytes()) {
                     eprintln!("Warning: Failed to write to log file: {}", e);
                 }
-                if let Err(e) = file.flush() {
+                if let Err(e) = writer.flush() {
                     eprintln!("Warning: Failed to flush log file: {}", e);
                 }
             }
+            if let LogSink::File(file_guard) = &mut *sink_guard {
+                self.roll_if_full(file_guard);
+            }
+        }
+    }
+    
+    /// Format one log line (including the trailing newline)
+    fn format_entry(&self, level: Level, message: &str) -> String {
+        let now = Local::now();
+        match self.format {
+            LogFormat::Text => format!("[{}] [{}] {}\n", now.format("%Y-%m-%d %H:%M:%S%.3f"), level, message),
+            // serde_json escapes quotes, backslashes and control characters, so
+            // paths and multi-line errors stay on one parseable line
+            LogFormat::Json => {
+                let entry = json!({
+                    "timestamp": now.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+                    "level": level.as_str(),
+                    "message": message,
+                });
+                format!("{}\n", entry)
+            }
         }
     }
     
     /// Log an info message
     pub fn log_info(&self, message: &str) {
-        self.write_log("INFO", message);
+        self.write_log(Level::Info, message);
     }
     
     /// Log a warning message
     pub fn log_warning(&self, message: &str) {
-        self.write_log("WARN", message);
+        self.write_log(Level::Warn, message);
     }
     
     /// Log an error message
     pub fn log_error(&self, message: &str) {
-        self.write_log("ERROR", message);
+        self.write_log(Level::Error, message);
     }
     
     /// Log a debug message
     pub fn log_debug(&self, message: &str) {
-        self.write_log("DEBUG", message);
+        self.write_log(Level::Debug, message);
     }
     
-    /// Get the log file path
-    pub fn get_log_path(&self) -> String {
+    /// Get the log file path, `None` when logging to a writer
+    pub fn get_log_path(&self) -> Option<String> {
+        let log_path = self.log_path.as_ref()?;
         // Ensure the log directory exists before returning the path
         if let Ok(log_dir) = Self::get_log_directory() {
             let _ = fs::create_dir_all(&log_dir);
         }
-        self.log_path.to_string_lossy().to_string()
+        Some(log_path.to_string_lossy().to_string())
+    }
+    
+    /// Read the last `count` lines of the current log file
+    /// The file is read backwards in chunks so large logs are never loaded whole.
+    /// Returns fewer lines if the file is shorter than `count`.
+    pub fn read_recent_lines(&self, count: usize) -> Result<Vec<String>, std::io::Error> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        
+        let log_path = self.log_path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::Unsupported, "Logs are not being written to a file")
+        })?;
+        let mut file = File::open(log_path)?;
+        let mut pos = file.metadata()?.len();
+        // Chunks from the end of the file backwards, joined once at the end
+        let mut chunks: Vec<Vec<u8>> = Vec::new();
+        let mut newlines = 0;
+        
+        // Stop once we hold more newlines than requested lines, which guarantees
+        // `count` complete lines (the log always ends with a newline)
+        while pos > 0 {
+            let chunk_size = TAIL_CHUNK_SIZE.min(pos);
+            pos -= chunk_size;
+            file.seek(SeekFrom::Start(pos))?;
+            
+            let mut chunk = vec![0u8; chunk_size as usize];
+            file.read_exact(&mut chunk)?;
+            newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+            chunks.push(chunk);
+            
+            if newlines > count {
+                break;
+            }
+        }
+        
+        let buffer: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+        let text = String::from_utf8_lossy(&buffer);
+        let lines: Vec<&str> = text.lines().collect();
+        let start = lines.len().saturating_sub(count);
+        
+        Ok(lines[start..].iter().map(|line| line.to_string()).collect())
     }
 }
 
@@ -141,16 +373,175 @@ mod tests {
     
     #[test]
     fn test_logger_creation() {
-        let logger = AppLogger::new();
+        let logger = AppLogger::new(LogFormat::Text);
         assert!(logger.is_ok());
     }
     
     #[test]
     fn test_logging() {
-        let logger = AppLogger::new().unwrap();
+        let logger = AppLogger::new(LogFormat::Text).unwrap();
         logger.log_info("Test info message");
         logger.log_warning("Test warning message");
         logger.log_error("Test error message");
         logger.log_debug("Test debug message");
     }
+    
+    /// Fresh scratch directory under the system temp dir
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    
+    /// File logger writing into a fresh scratch directory
+    fn scratch_logger(name: &str, format: LogFormat) -> (AppLogger, PathBuf) {
+        let dir = scratch_dir(name);
+        let logger = AppLogger::in_directory(&dir, format, RotationPolicy::default()).unwrap();
+        (logger, dir)
+    }
+    
+    #[test]
+    fn test_log_level_filter() {
+        let (logger, dir) = scratch_logger("xblackbox_test_level_filter", LogFormat::Text);
+        logger.set_level(LevelFilter::Warn);
+        assert_eq!(logger.level(), LevelFilter::Warn);
+        logger.log_debug("Level filter marker debug");
+        logger.log_info("Level filter marker info");
+        logger.log_warning("Level filter marker warn");
+        
+        let lines = logger.read_recent_lines(usize::MAX).unwrap();
+        assert!(lines.iter().any(|l| l.ends_with("Level filter marker warn")));
+        assert!(!lines.iter().any(|l| l.ends_with("Level filter marker info")));
+        assert!(!lines.iter().any(|l| l.ends_with("Level filter marker debug")));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_json_log_format() {
+        let (logger, dir) = scratch_logger("xblackbox_test_json_format", LogFormat::Json);
+        let message = "JSON marker \"quoted\" C:\\flights\\a.xdr\nsecond line\ttab";
+        logger.log_warning(message);
+        
+        let lines = logger.read_recent_lines(usize::MAX).unwrap();
+        let entry = lines
+            .iter()
+            .filter_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())
+            .find(|v| v["message"] == message)
+            .expect("JSON entry not found on a single line");
+        assert_eq!(entry["level"], "WARN");
+        assert!(entry["timestamp"].as_str().is_some_and(|t| t.contains('T')));
+        
+        assert_eq!(LogFormat::from_name(" JSON "), Some(LogFormat::Json));
+        assert_eq!(LogFormat::from_name("xml"), None);
+        assert_eq!(LogFormat::default(), LogFormat::Text);
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_rotate_logs_by_size() {
+        let dir = scratch_dir("xblackbox_test_rotate_size");
+        let now = std::time::SystemTime::now();
+        // Four 1000-byte logs, day 0 newest
+        for day in 0..4u64 {
+            let path = dir.join(format!("xblackbox_2024010{}.log", day));
+            fs::write(&path, vec![b'x'; 1000]).unwrap();
+            let file = File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(day * 86_400)).unwrap();
+        }
+        let current = dir.join("xblackbox_20240100.log");
+        
+        let policy = RotationPolicy {
+            max_total_bytes: 2500,
+            ..RotationPolicy::default()
+        };
+        AppLogger::rotate_logs(&dir, &policy, &current).unwrap();
+        assert!(current.exists());
+        assert!(dir.join("xblackbox_20240101.log").exists());
+        assert!(!dir.join("xblackbox_20240102.log").exists());
+        assert!(!dir.join("xblackbox_20240103.log").exists());
+        
+        // The file count bound still applies under the size budget
+        let policy = RotationPolicy {
+            max_files: 1,
+            ..RotationPolicy::default()
+        };
+        AppLogger::rotate_logs(&dir, &policy, &current).unwrap();
+        assert!(current.exists());
+        assert!(!dir.join("xblackbox_20240101.log").exists());
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_roll_file() {
+        let dir = scratch_dir("xblackbox_test_roll");
+        let path = dir.join("xblackbox_20240101.log");
+        
+        fs::write(&path, "first").unwrap();
+        let rolled = AppLogger::roll_file(&path).unwrap();
+        assert_eq!(rolled, dir.join("xblackbox_20240101.1.log"));
+        assert!(!path.exists());
+        
+        fs::write(&path, "second").unwrap();
+        assert_eq!(AppLogger::roll_file(&path).unwrap(), dir.join("xblackbox_20240101.2.log"));
+        assert_eq!(fs::read_to_string(dir.join("xblackbox_20240101.1.log")).unwrap(), "first");
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    /// Cloneable in-memory sink so a test can inspect what was logged
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<Mutex<Vec<u8>>>);
+    
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    
+    #[test]
+    fn test_with_writer() {
+        let buffer = SharedBuffer::default();
+        let logger = AppLogger::with_writer(LogFormat::Text, Box::new(buffer.clone()));
+        logger.set_level(LevelFilter::Info);
+        logger.log_info("Writer marker info");
+        logger.log_debug("Writer marker debug");
+        
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(text.ends_with("[INFO] Writer marker info\n"));
+        assert!(!text.contains("Writer marker debug"));
+        assert!(logger.get_log_path().is_none());
+        assert!(logger.read_recent_lines(10).is_err());
+    }
+    
+    #[test]
+    fn test_read_recent_lines() {
+        let (logger, dir) = scratch_logger("xblackbox_test_recent_lines", LogFormat::Text);
+        logger.set_level(LevelFilter::Info);
+        // Enough lines to span several read chunks
+        for i in 0..1000 {
+            logger.log_info(&format!("Recent lines marker {}", i));
+        }
+        
+        let lines = logger.read_recent_lines(10).unwrap();
+        assert_eq!(lines.len(), 10);
+        assert!(lines[0].ends_with("Recent lines marker 990"));
+        assert!(lines[9].ends_with("Recent lines marker 999"));
+        
+        // Asking for more lines than exist returns the whole file: two startup
+        // lines, then the markers in order
+        let all_lines = logger.read_recent_lines(usize::MAX).unwrap();
+        assert_eq!(all_lines.len(), 1002);
+        assert!(all_lines[2].ends_with("Recent lines marker 0"));
+        assert!(logger.read_recent_lines(0).unwrap().is_empty());
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,288 @@
+use crate::xdr::{AirportInfo, Statistics, XDRData};
+use crate::FlightAnalysis;
+use serde::Deserialize;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Output format of a flight report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+}
+
+/// Make text safe inside a Markdown table cell
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn airport_label(airport: Option<&AirportInfo>) -> String {
+    match airport {
+        Some(a) if a.name.is_empty() => md_cell(&a.icao),
+        Some(a) => md_cell(&format!("{} ({})", a.icao, a.name)),
+        None => "-".to_string(),
+    }
+}
+
+fn optional(value: Option<f64>, precision: usize) -> String {
+    value.map_or_else(|| "-".to_string(), |v| format!("{:.*}", precision, v))
+}
+
+impl XDRData {
+    /// Index of the first dataref whose lowercase name contains any pattern
+    fn find_dataref(&self, patterns: &[&str]) -> Option<usize> {
+        self.datarefs.iter().position(|dr| {
+            let name = dr.name.to_lowercase();
+            patterns.iter().any(|p| name.contains(p))
+        })
+    }
+
+    /// Statistics for the altitude, speed and vertical speed datarefs that exist
+    fn key_statistics(&self) -> Vec<(String, Statistics)> {
+        let altitude = self.find_position_datarefs().2;
+        let speed = self
+            .find_dataref(&["indicated_airspeed"])
+            .or_else(|| self.find_dataref(&["groundspeed", "ground_speed"]));
+        let vertical_speed = self.find_vertical_speed_dataref();
+
+        [altitude, speed, vertical_speed]
+            .into_iter()
+            .flatten()
+            .filter_map(|idx| {
+                let stats = self.get_parameter_statistics(idx, 0, None)?;
+                Some((self.display_name(&self.datarefs[idx].name), stats))
+            })
+            .collect()
+    }
+
+    /// Markdown flight report: recording metadata, summary, phases, key
+    /// statistics and anomalies, assembled from a completed analysis
+    pub(crate) fn generate_report(&self, analysis: &FlightAnalysis) -> String {
+        let mut out = String::new();
+        let header = &self.header;
+        let file_name = Path::new(&self.filepath)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "XBlackBox flight".to_string());
+
+        // Writing to a String cannot fail
+        let _ = writeln!(out, "# Flight Report: {}\n", file_name);
+
+        let _ = writeln!(out, "## Recording\n");
+        let _ = writeln!(out, "| Field | Value |");
+        let _ = writeln!(out, "|---|---|");
+        let _ = writeln!(out, "| Format version | {} |", header.version);
+        let _ = writeln!(out, "| Recording level | {} |", md_cell(&header.level_name));
+        let _ = writeln!(out, "| Sample interval | {} s |", header.interval);
+        let _ = writeln!(out, "| Start | {} |", md_cell(&header.start_datetime));
+        let _ = writeln!(out, "| End | {} |", md_cell(header.end_datetime.as_deref().unwrap_or("-")));
+        let _ = writeln!(out, "| Departure | {} |", airport_label(header.departure_airport.as_ref()));
+        let _ = writeln!(out, "| Arrival | {} |", airport_label(header.arrival_airport.as_ref()));
+        let _ = writeln!(out, "| Frames | {} |", self.frames.len());
+        let _ = writeln!(out, "| Complete | {} |\n", if self.is_complete() { "yes" } else { "no (truncated)" });
+
+        let _ = writeln!(out, "## Summary\n");
+        let _ = writeln!(out, "| Metric | Value |");
+        let _ = writeln!(out, "|---|---|");
+        let _ = writeln!(out, "| Total flight time | {:.1} s |", analysis.total_flight_time);
+        let _ = writeln!(out, "| Max altitude | {:.1} |", analysis.max_altitude);
+        let _ = writeln!(out, "| Max speed | {:.1} |", analysis.max_speed);
+        let _ = writeln!(out, "| Average fuel flow | {} |", optional(analysis.average_fuel_flow, 3));
+        let _ = writeln!(out, "| Landing G | {} |", optional(analysis.landing_g_force, 2));
+        let _ = writeln!(out, "| Landing quality | {} |", analysis.landing_quality.as_deref().unwrap_or("-"));
+        let _ = writeln!(out, "| Max climb rate | {} |", optional(analysis.max_climb_rate, 0));
+        let _ = writeln!(out, "| Max descent rate | {} |\n", optional(analysis.max_descent_rate, 0));
+
+        if let Some(approach) = &analysis.approach_analysis {
+            let _ = writeln!(out, "## Approach\n");
+            let _ = writeln!(out, "- Stable approach: {}", if approach.stable_approach { "yes" } else { "no" });
+            let _ = writeln!(out, "- Average descent rate: {:.0}", approach.average_descent_rate);
+            let _ = writeln!(out, "- Touchdown speed: {:.1}", approach.touchdown_speed);
+            let _ = writeln!(out, "- Final approach altitude: {:.1}\n", approach.final_approach_altitude);
+        }
+
+        let _ = writeln!(out, "## Phases\n");
+        if analysis.phases.is_empty() {
+            let _ = writeln!(out, "No flight phases detected.\n");
+        } else {
+            let _ = writeln!(out, "| Phase | Start (s) | End (s) | Duration (s) | Avg altitude | Avg speed |");
+            let _ = writeln!(out, "|---|---|---|---|---|---|");
+            for phase in &analysis.phases {
+                let _ = writeln!(
+                    out,
+                    "| {} | {:.1} | {:.1} | {:.1} | {} | {} |",
+                    md_cell(&phase.name),
+                    phase.start_time,
+                    phase.end_time,
+                    phase.duration,
+                    optional(phase.average_altitude, 0),
+                    optional(phase.average_speed, 1)
+                );
+            }
+            let _ = writeln!(out);
+        }
+
+        let key_stats = self.key_statistics();
+        if !key_stats.is_empty() {
+            let _ = writeln!(out, "## Key Statistics\n");
+            let _ = writeln!(out, "| Parameter | Min | Max | Mean | Std |");
+            let _ = writeln!(out, "|---|---|---|---|---|");
+            for (name, stats) in &key_stats {
+                let _ = writeln!(
+                    out,
+                    "| {} | {:.1} | {:.1} | {:.1} | {:.1} |",
+                    md_cell(name),
+                    stats.min,
+                    stats.max,
+                    stats.mean,
+                    stats.std
+                );
+            }
+            let _ = writeln!(out);
+        }
+
+        let _ = writeln!(out, "## Anomalies\n");
+        if analysis.anomalies.is_empty() {
+            let _ = writeln!(out, "No anomalies detected.");
+        } else {
+            let _ = writeln!(out, "| Start (s) | End (s) | Severity | Parameter | Description | Peak |");
+            let _ = writeln!(out, "|---|---|---|---|---|---|");
+            for anomaly in &analysis.anomalies {
+                let _ = writeln!(
+                    out,
+                    "| {:.1} | {:.1} | {} | {} | {} | {:.2} |",
+                    anomaly.timestamp,
+                    anomaly.end_timestamp,
+                    md_cell(&anomaly.severity),
+                    md_cell(&anomaly.parameter),
+                    md_cell(&anomaly.description),
+                    anomaly.peak_value
+                );
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xdr::{DataFrame, DataValue, DatarefDef};
+    use crate::{Anomaly, FlightPhase};
+
+    #[test]
+    fn test_markdown_report() {
+        let mut data = XDRData::new();
+        data.filepath = "/recordings/flight.xdr".to_string();
+        data.header.version = 2;
+        data.header.level_name = "Simple".to_string();
+        data.header.interval = 1.0;
+        data.header.start_datetime = "2023-11-14 22:13:20 UTC".to_string();
+        data.header.departure_airport = Some(AirportInfo {
+            icao: "KSFO".to_string(),
+            name: "San Francisco Intl".to_string(),
+            lat: 37.619,
+            lon: -122.375,
+            valid: true,
+        });
+        for name in ["sim/flightmodel/position/elevation", "sim/flightmodel/position/indicated_airspeed"] {
+            data.datarefs.push(DatarefDef {
+                name: name.to_string(),
+                data_type: "float".to_string(),
+                array_size: 0,
+            });
+        }
+        for (i, (alt, ias)) in [(10.0, 0.0), (300.0, 120.0), (600.0, 140.0)].into_iter().enumerate() {
+            data.frames.push(DataFrame {
+                timestamp: i as f32,
+                values: vec![DataValue::Float(alt), DataValue::Float(ias)],
+            });
+        }
+
+        let analysis = FlightAnalysis {
+            phases: vec![FlightPhase {
+                name: "Climb".to_string(),
+                start_time: 0.0,
+                end_time: 2.0,
+                duration: 2.0,
+                average_altitude: Some(303.3),
+                average_speed: Some(86.7),
+                landing_g_force: None,
+                fuel_used: None,
+                average_fuel_flow: None,
+            }],
+            total_flight_time: 2.0,
+            max_altitude: 600.0,
+            max_speed: 140.0,
+            average_fuel_flow: None,
+            landing_g_force: None,
+            landing_quality: None,
+            max_climb_rate: Some(1500.0),
+            max_descent_rate: None,
+            approach_analysis: None,
+            anomalies: vec![Anomaly {
+                timestamp: 1.0,
+                end_timestamp: 2.0,
+                frame_index: 1,
+                severity: "medium".to_string(),
+                description: "Climb | steep".to_string(),
+                parameter: "Vertical Speed".to_string(),
+                value: 1500.0,
+                peak_value: 1800.0,
+            }],
+        };
+
+        let expected = "\
+# Flight Report: flight.xdr
+
+## Recording
+
+| Field | Value |
+|---|---|
+| Format version | 2 |
+| Recording level | Simple |
+| Sample interval | 1 s |
+| Start | 2023-11-14 22:13:20 UTC |
+| End | - |
+| Departure | KSFO (San Francisco Intl) |
+| Arrival | - |
+| Frames | 3 |
+| Complete | no (truncated) |
+
+## Summary
+
+| Metric | Value |
+|---|---|
+| Total flight time | 2.0 s |
+| Max altitude | 600.0 |
+| Max speed | 140.0 |
+| Average fuel flow | - |
+| Landing G | - |
+| Landing quality | - |
+| Max climb rate | 1500 |
+| Max descent rate | - |
+
+## Phases
+
+| Phase | Start (s) | End (s) | Duration (s) | Avg altitude | Avg speed |
+|---|---|---|---|---|---|
+| Climb | 0.0 | 2.0 | 2.0 | 303 | 86.7 |
+
+## Key Statistics
+
+| Parameter | Min | Max | Mean | Std |
+|---|---|---|---|---|
+| Altitude MSL (m) | 10.0 | 600.0 | 303.3 | 240.9 |
+| Indicated Airspeed (kts) | 0.0 | 140.0 | 86.7 | 61.8 |
+
+## Anomalies
+
+| Start (s) | End (s) | Severity | Parameter | Description | Peak |
+|---|---|---|---|---|---|
+| 1.0 | 2.0 | medium | Vertical Speed | Climb \\| steep | 1800.00 |
+";
+        assert_eq!(data.generate_report(&analysis), expected);
+    }
+}
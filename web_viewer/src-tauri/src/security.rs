@@ -1,18 +1,20 @@
 use std::path::{Path, PathBuf};
 use std::fs;
 
-/// Maximum file size allowed (500MB)
-const MAX_FILE_SIZE: u64 = 500 * 1024 * 1024;
+/// Default maximum file size allowed (500MB)
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 500 * 1024 * 1024;
 
 /// Security validation errors
 #[derive(Debug)]
 pub enum SecurityError {
     InvalidPath(String),
     _PathTraversal(String),
-    FileTooBig(u64),
+    FileTooBig(u64, u64),
     FileNotFound(String),
     InvalidExtension(String),
     PermissionDenied(String),
+    OutsideAllowedDirectory(String),
+    SymlinkNotAllowed(String),
 }
 
 impl std::fmt::Display for SecurityError {
@@ -20,18 +22,69 @@ impl std::fmt::Display for SecurityError {
         match self {
             SecurityError::InvalidPath(msg) => write!(f, "Invalid path: {}", msg),
             SecurityError::_PathTraversal(msg) => write!(f, "Path traversal detected: {}", msg),
-            SecurityError::FileTooBig(size) => write!(f, "File too large: {} bytes (max: {} bytes)", size, MAX_FILE_SIZE),
+            SecurityError::FileTooBig(size, max) => write!(f, "File too large: {} bytes (max: {} bytes)", size, max),
             SecurityError::FileNotFound(msg) => write!(f, "File not found: {}", msg),
             SecurityError::InvalidExtension(msg) => write!(f, "Invalid file extension: {}", msg),
             SecurityError::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
+            SecurityError::OutsideAllowedDirectory(msg) => write!(f, "Outside allowed directories: {}", msg),
+            SecurityError::SymlinkNotAllowed(msg) => write!(f, "Symbolic link not allowed: {}", msg),
         }
     }
 }
 
 impl std::error::Error for SecurityError {}
 
+/// Every check applied by `validate_file_path_with_policy`
+#[derive(Debug, Clone)]
+pub struct PathPolicy {
+    /// Directories the file must lie within (empty means no restriction)
+    pub allowed_dirs: Vec<PathBuf>,
+    /// Maximum file size in bytes
+    pub max_bytes: u64,
+    /// Whether a path that is itself a symlink may be resolved. Locked-down
+    /// deployments can disable this so a link can't redirect a permitted path.
+    pub follow_symlinks: bool,
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        PathPolicy {
+            allowed_dirs: Vec::new(),
+            max_bytes: DEFAULT_MAX_FILE_SIZE,
+            follow_symlinks: true,
+        }
+    }
+}
+
+#[cfg(test)]
 /// Validate and sanitize a file path for XDR file loading
 pub fn validate_file_path(path_str: &str) -> Result<PathBuf, SecurityError> {
+    validate_file_path_with_policy(path_str, &PathPolicy::default())
+}
+
+#[cfg(test)]
+/// Validate a file path and require it to lie within one of `allowed`
+/// (an empty allowlist means no directory restriction)
+pub fn validate_file_path_within(path_str: &str, allowed: &[PathBuf]) -> Result<PathBuf, SecurityError> {
+    let policy = PathPolicy {
+        allowed_dirs: allowed.to_vec(),
+        ..PathPolicy::default()
+    };
+    validate_file_path_with_policy(path_str, &policy)
+}
+
+#[cfg(test)]
+/// Validate a file path with a custom maximum file size in bytes
+pub fn validate_file_path_with_limit(path_str: &str, max_bytes: u64) -> Result<PathBuf, SecurityError> {
+    let policy = PathPolicy {
+        max_bytes,
+        ..PathPolicy::default()
+    };
+    validate_file_path_with_policy(path_str, &policy)
+}
+
+/// Validate a file path against an explicit policy
+pub fn validate_file_path_with_policy(path_str: &str, policy: &PathPolicy) -> Result<PathBuf, SecurityError> {
     // Check for empty path
     if path_str.trim().is_empty() {
         return Err(SecurityError::InvalidPath("Path cannot be empty".to_string()));
@@ -40,6 +93,16 @@ pub fn validate_file_path(path_str: &str) -> Result<PathBuf, SecurityError> {
     // Create path object
     let path = Path::new(path_str);
     
+    // Canonicalization follows links silently, so check the original path first
+    if !policy.follow_symlinks
+        && fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+    {
+        return Err(SecurityError::SymlinkNotAllowed(format!(
+            "Path is a symbolic link: {}",
+            path_str
+        )));
+    }
+    
     // Canonicalize the path to resolve any symlinks and relative paths
     let canonical_path = path.canonicalize().map_err(|e| {
         match e.kind() {
@@ -56,13 +119,37 @@ pub fn validate_file_path(path_str: &str) -> Result<PathBuf, SecurityError> {
     })?;
     
     // Note: Canonicalization resolves all relative path components (including ..)
-    // to absolute paths. The path is now safe from traversal attacks.
-    // If additional directory restrictions are needed in the future, validate
-    // that the canonical path is within allowed directories here.
+    // to absolute paths. The path is now safe from traversal attacks, so the
+    // allowlist check below compares canonical paths only.
+    if !policy.allowed_dirs.is_empty() {
+        let inside = policy
+            .allowed_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| canonical_path.starts_with(dir));
+        if !inside {
+            return Err(SecurityError::OutsideAllowedDirectory(format!(
+                "File is not within an allowed directory: {}",
+                path_str
+            )));
+        }
+    }
     
-    // Validate file extension
+    // Validate file extension (.xdr, or gzip-compressed .xdr.gz)
     match canonical_path.extension() {
         Some(ext) if ext.to_string_lossy().eq_ignore_ascii_case("xdr") => {},
+        Some(ext) if ext.to_string_lossy().eq_ignore_ascii_case("gz") => {
+            let inner_is_xdr = canonical_path
+                .file_stem()
+                .map(Path::new)
+                .and_then(|stem| stem.extension())
+                .is_some_and(|inner| inner.to_string_lossy().eq_ignore_ascii_case("xdr"));
+            if !inner_is_xdr {
+                return Err(SecurityError::InvalidExtension(
+                    "Expected .xdr.gz for compressed files".to_string()
+                ));
+            }
+        }
         Some(ext) => {
             return Err(SecurityError::InvalidExtension(
                 format!("Expected .xdr file, got .{}", ext.to_string_lossy())
@@ -87,8 +174,8 @@ pub fn validate_file_path(path_str: &str) -> Result<PathBuf, SecurityError> {
     }
     
     let file_size = metadata.len();
-    if file_size > MAX_FILE_SIZE {
-        return Err(SecurityError::FileTooBig(file_size));
+    if file_size > policy.max_bytes {
+        return Err(SecurityError::FileTooBig(file_size, policy.max_bytes));
     }
     
     if file_size == 0 {
@@ -100,6 +187,88 @@ pub fn validate_file_path(path_str: &str) -> Result<PathBuf, SecurityError> {
     Ok(canonical_path)
 }
 
+/// Validate a path an export will be written to. The file need not exist, but
+/// its parent directory must; the directory is canonicalized and checked
+/// against the policy's allowlist, and the file name must end in one of
+/// `extensions` (given without the leading dot, e.g. `"csv"` or `"xdr.gz"`).
+pub fn validate_output_path(
+    path_str: &str,
+    extensions: &[&str],
+    policy: &PathPolicy,
+) -> Result<PathBuf, SecurityError> {
+    if path_str.trim().is_empty() {
+        return Err(SecurityError::InvalidPath("Path cannot be empty".to_string()));
+    }
+
+    let path = Path::new(path_str);
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| SecurityError::InvalidPath(format!("Path has no file name: {}", path_str)))?;
+
+    let name_lower = file_name.to_string_lossy().to_lowercase();
+    if !extensions.iter().any(|ext| name_lower.ends_with(&format!(".{}", ext.to_lowercase()))) {
+        return Err(SecurityError::InvalidExtension(format!(
+            "Expected one of: {}",
+            extensions.iter().map(|ext| format!(".{}", ext)).collect::<Vec<_>>().join(", ")
+        )));
+    }
+
+    // An existing link at the target would redirect the write elsewhere
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if metadata.file_type().is_symlink() && !policy.follow_symlinks {
+            return Err(SecurityError::SymlinkNotAllowed(format!(
+                "Path is a symbolic link: {}",
+                path_str
+            )));
+        }
+    }
+
+    let parent = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let canonical_parent = parent.canonicalize().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            SecurityError::FileNotFound(format!("Directory does not exist: {}", parent.display()))
+        }
+        std::io::ErrorKind::PermissionDenied => {
+            SecurityError::PermissionDenied(format!("Cannot access directory: {}", parent.display()))
+        }
+        _ => SecurityError::InvalidPath(format!("Cannot resolve path: {}", e)),
+    })?;
+
+    // An existing target may be a link; the allowlist applies to where the
+    // write actually lands, and a dangling link can't be resolved safely
+    let mut output_path = canonical_parent.join(file_name);
+    if fs::symlink_metadata(&output_path).is_ok() {
+        output_path = output_path
+            .canonicalize()
+            .map_err(|e| SecurityError::InvalidPath(format!("Cannot resolve path: {}", e)))?;
+    }
+
+    if !policy.allowed_dirs.is_empty() {
+        let inside = policy
+            .allowed_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| output_path.starts_with(dir));
+        if !inside {
+            return Err(SecurityError::OutsideAllowedDirectory(format!(
+                "File is not within an allowed directory: {}",
+                path_str
+            )));
+        }
+    }
+
+    if output_path.is_dir() {
+        return Err(SecurityError::InvalidPath(
+            "Path points to a directory".to_string()
+        ));
+    }
+
+    Ok(output_path)
+}
+
 /// Sanitize error messages to prevent information leakage
 /// Note: This assumes error strings may contain file paths.
 /// For non-path errors, it simply truncates to 200 chars.
@@ -144,6 +313,164 @@ mod tests {
         let _ = fs::remove_file(&test_file);
     }
     
+    #[test]
+    fn test_gzip_extension() {
+        let temp_dir = std::env::temp_dir();
+        let good_file = temp_dir.join("test_security.xdr.gz");
+        let bad_file = temp_dir.join("test_security.txt.gz");
+        File::create(&good_file).unwrap().write_all(b"data").unwrap();
+        File::create(&bad_file).unwrap().write_all(b"data").unwrap();
+        
+        assert!(validate_file_path(good_file.to_str().unwrap()).is_ok());
+        assert!(matches!(
+            validate_file_path(bad_file.to_str().unwrap()),
+            Err(SecurityError::InvalidExtension(_))
+        ));
+        
+        let _ = fs::remove_file(&good_file);
+        let _ = fs::remove_file(&bad_file);
+    }
+    
+    #[test]
+    fn test_allowed_directories() {
+        let base = std::env::temp_dir().join("test_security_allow");
+        let allowed = base.join("allowed");
+        let other = base.join("other");
+        fs::create_dir_all(&allowed).unwrap();
+        fs::create_dir_all(&other).unwrap();
+        let inside_file = allowed.join("flight.xdr");
+        let outside_file = other.join("flight.xdr");
+        File::create(&inside_file).unwrap().write_all(b"data").unwrap();
+        File::create(&outside_file).unwrap().write_all(b"data").unwrap();
+        
+        let allowlist = vec![allowed.clone()];
+        assert!(validate_file_path_within(inside_file.to_str().unwrap(), &allowlist).is_ok());
+        assert!(matches!(
+            validate_file_path_within(outside_file.to_str().unwrap(), &allowlist),
+            Err(SecurityError::OutsideAllowedDirectory(_))
+        ));
+        
+        // `..` components must not escape the allowlist
+        let escape = allowed.join("..").join("other").join("flight.xdr");
+        assert!(matches!(
+            validate_file_path_within(escape.to_str().unwrap(), &allowlist),
+            Err(SecurityError::OutsideAllowedDirectory(_))
+        ));
+        
+        let _ = fs::remove_dir_all(&base);
+    }
+    
+    #[test]
+    fn test_file_size_limit() {
+        let test_file = std::env::temp_dir().join("test_security_limit.xdr");
+        File::create(&test_file).unwrap().write_all(&[0u8; 64]).unwrap();
+        let path = test_file.to_str().unwrap();
+        
+        assert!(validate_file_path_with_limit(path, 64).is_ok());
+        let err = validate_file_path_with_limit(path, 32).unwrap_err();
+        assert!(matches!(err, SecurityError::FileTooBig(64, 32)));
+        assert!(err.to_string().contains("max: 32 bytes"));
+        
+        let _ = fs::remove_file(&test_file);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_policy() {
+        let base = std::env::temp_dir().join("test_security_symlink");
+        fs::create_dir_all(&base).unwrap();
+        let target = base.join("target.xdr");
+        let link = base.join("link.xdr");
+        File::create(&target).unwrap().write_all(b"data").unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let link_str = link.to_str().unwrap();
+        
+        assert!(validate_file_path(link_str).is_ok());
+        
+        let strict = PathPolicy {
+            follow_symlinks: false,
+            ..PathPolicy::default()
+        };
+        assert!(matches!(
+            validate_file_path_with_policy(link_str, &strict),
+            Err(SecurityError::SymlinkNotAllowed(_))
+        ));
+        assert!(validate_file_path_with_policy(target.to_str().unwrap(), &strict).is_ok());
+        
+        let _ = fs::remove_dir_all(&base);
+    }
+    
+    #[test]
+    fn test_output_path() {
+        let base = std::env::temp_dir().join("test_security_output");
+        let allowed = base.join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        let policy = PathPolicy::default();
+        
+        // The file itself doesn't have to exist yet
+        let target = allowed.join("..").join("allowed").join("export.CSV");
+        let validated = validate_output_path(target.to_str().unwrap(), &["csv"], &policy).unwrap();
+        assert_eq!(validated, allowed.canonicalize().unwrap().join("export.CSV"));
+        
+        assert!(matches!(
+            validate_output_path(allowed.join("export.txt").to_str().unwrap(), &["csv"], &policy),
+            Err(SecurityError::InvalidExtension(_))
+        ));
+        assert!(validate_output_path(allowed.join("cut.xdr.gz").to_str().unwrap(), &["xdr", "xdr.gz"], &policy).is_ok());
+        assert!(matches!(
+            validate_output_path(base.join("missing").join("export.csv").to_str().unwrap(), &["csv"], &policy),
+            Err(SecurityError::FileNotFound(_))
+        ));
+        fs::create_dir_all(allowed.join("dir.csv")).unwrap();
+        assert!(matches!(
+            validate_output_path(allowed.join("dir.csv").to_str().unwrap(), &["csv"], &policy),
+            Err(SecurityError::InvalidPath(_))
+        ));
+        
+        let restricted = PathPolicy {
+            allowed_dirs: vec![allowed.clone()],
+            ..PathPolicy::default()
+        };
+        assert!(validate_output_path(allowed.join("export.csv").to_str().unwrap(), &["csv"], &restricted).is_ok());
+        assert!(matches!(
+            validate_output_path(base.join("export.csv").to_str().unwrap(), &["csv"], &restricted),
+            Err(SecurityError::OutsideAllowedDirectory(_))
+        ));
+        
+        let _ = fs::remove_dir_all(&base);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_output_path_symlink() {
+        let base = std::env::temp_dir().join("test_security_output_symlink");
+        let allowed = base.join("allowed");
+        fs::create_dir_all(&allowed).unwrap();
+        let outside = base.join("outside.csv");
+        let link = allowed.join("link.csv");
+        File::create(&outside).unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        let link_str = link.to_str().unwrap();
+        
+        // The write lands on the link target, which must pass the allowlist itself
+        let restricted = PathPolicy {
+            allowed_dirs: vec![allowed.clone()],
+            ..PathPolicy::default()
+        };
+        assert!(matches!(
+            validate_output_path(link_str, &["csv"], &restricted),
+            Err(SecurityError::OutsideAllowedDirectory(_))
+        ));
+        assert_eq!(
+            validate_output_path(link_str, &["csv"], &PathPolicy::default()).unwrap(),
+            outside.canonicalize().unwrap()
+        );
+        
+        let _ = fs::remove_dir_all(&base);
+    }
+    
     #[test]
     fn test_nonexistent_file() {
         let result = validate_file_path("/nonexistent/path/to/file.xdr");
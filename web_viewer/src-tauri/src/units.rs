@@ -0,0 +1,135 @@
+use crate::xdr::{METERS_PER_FOOT, METERS_PER_NM};
+use serde::{Deserialize, Serialize};
+
+/// Display unit system for converted parameter values
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    Imperial,
+    Metric,
+}
+
+/// Recorded (native X-Plane) unit of a dataref
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Unit {
+    Meters,
+    Feet,
+    Knots,
+    MetersPerSecond,
+    FeetPerMinute,
+}
+
+/// Native units keyed by dataref name pattern; the first matching pattern wins
+const UNIT_TABLE: &[(&str, Unit)] = &[
+    ("vh_ind_fpm", Unit::FeetPerMinute),
+    ("_vvi_fpm", Unit::FeetPerMinute),
+    ("autopilot/vertical_velocity", Unit::FeetPerMinute),
+    ("autopilot/altitude", Unit::Feet),
+    ("autopilot/airspeed", Unit::Knots),
+    ("altitude_ft", Unit::Feet),
+    ("position/elevation", Unit::Meters),
+    ("position/y_agl", Unit::Meters),
+    ("_msl_m", Unit::Meters),
+    ("indicated_airspeed", Unit::Knots),
+    ("_kt", Unit::Knots),
+    ("position/groundspeed", Unit::MetersPerSecond),
+    ("true_airspeed", Unit::MetersPerSecond),
+];
+
+impl Unit {
+    /// Factor converting one of this unit into its SI base unit (m or m/s)
+    fn to_si(self) -> f64 {
+        match self {
+            Unit::Meters | Unit::MetersPerSecond => 1.0,
+            Unit::Feet => METERS_PER_FOOT,
+            Unit::Knots => METERS_PER_NM / 3600.0,
+            Unit::FeetPerMinute => METERS_PER_FOOT / 60.0,
+        }
+    }
+
+    /// Label of the unit itself
+    fn label(self) -> &'static str {
+        match self {
+            Unit::Meters => "m",
+            Unit::Feet => "ft",
+            Unit::Knots => "kt",
+            Unit::MetersPerSecond => "m/s",
+            Unit::FeetPerMinute => "fpm",
+        }
+    }
+
+    /// Display unit (SI factor and label) for the same quantity in `system`
+    fn display(self, system: UnitSystem) -> (f64, &'static str) {
+        match (self, system) {
+            (Unit::Meters | Unit::Feet, UnitSystem::Metric) => (1.0, "m"),
+            (Unit::Meters | Unit::Feet, UnitSystem::Imperial) => (METERS_PER_FOOT, "ft"),
+            (Unit::Knots | Unit::MetersPerSecond, UnitSystem::Metric) => (1000.0 / 3600.0, "km/h"),
+            (Unit::Knots | Unit::MetersPerSecond, UnitSystem::Imperial) => (METERS_PER_NM / 3600.0, "kt"),
+            (Unit::FeetPerMinute, UnitSystem::Metric) => (1.0, "m/s"),
+            (Unit::FeetPerMinute, UnitSystem::Imperial) => (METERS_PER_FOOT / 60.0, "fpm"),
+        }
+    }
+}
+
+fn native_unit(name: &str) -> Option<Unit> {
+    let name_lower = name.to_lowercase();
+    UNIT_TABLE
+        .iter()
+        .find(|(pattern, _)| name_lower.contains(pattern))
+        .map(|(_, unit)| *unit)
+}
+
+/// Multiplier and unit label converting a dataref's recorded values to `to`.
+/// Returns `None` for datarefs without a known unit.
+pub fn conversion(name: &str, to: UnitSystem) -> Option<(f64, &'static str)> {
+    let unit = native_unit(name)?;
+    let (display_si, label) = unit.display(to);
+    Some((unit.to_si() / display_si, label))
+}
+
+/// Label of the unit a dataref is recorded in, e.g. "kt" or "m/s"
+pub fn native_label(name: &str) -> Option<&'static str> {
+    native_unit(name).map(Unit::label)
+}
+
+/// Convert a recorded value to `to`; unrecognized datarefs are left unchanged
+pub fn convert_value(name: &str, value: f64, to: UnitSystem) -> f64 {
+    match conversion(name, to) {
+        Some((factor, _)) => value * factor,
+        None => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-6, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_convert_value() {
+        assert_close(convert_value("sim/flightmodel/position/elevation", 100.0, UnitSystem::Imperial), 328.083_989_5);
+        assert_close(convert_value("sim/flightmodel/position/elevation", 100.0, UnitSystem::Metric), 100.0);
+        assert_close(convert_value("sim/flightmodel/position/groundspeed", 10.0, UnitSystem::Metric), 36.0);
+        assert_close(convert_value("sim/flightmodel/position/indicated_airspeed", 100.0, UnitSystem::Metric), 185.2);
+        assert_close(convert_value("sim/flightmodel/position/vh_ind_fpm", 1000.0, UnitSystem::Metric), 5.08);
+        assert_close(convert_value("sim/flightmodel/position/theta", 12.5, UnitSystem::Metric), 12.5);
+    }
+
+    #[test]
+    fn test_conversion_labels() {
+        assert_eq!(conversion("sim/flightmodel/position/vh_ind_fpm", UnitSystem::Imperial), Some((1.0, "fpm")));
+        assert_eq!(conversion("sim/cockpit2/pressurization/indicators/cabin_altitude_ft", UnitSystem::Metric).map(|c| c.1), Some("m"));
+        assert_eq!(conversion("sim/weather/wind_speed_kt", UnitSystem::Metric).map(|c| c.1), Some("km/h"));
+        assert!(conversion("sim/flightmodel/forces/g_nrml", UnitSystem::Metric).is_none());
+    }
+
+    #[test]
+    fn test_native_label() {
+        assert_eq!(native_label("sim/flightmodel/position/indicated_airspeed"), Some("kt"));
+        assert_eq!(native_label("sim/flightmodel/position/groundspeed"), Some("m/s"));
+        assert_eq!(native_label("sim/flightmodel/forces/g_nrml"), None);
+    }
+}
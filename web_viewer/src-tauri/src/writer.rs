@@ -0,0 +1,318 @@
+use crate::xdr::{
+    AirportInfo, DataValue, DatarefDef, XDRData, CHECKSUM_MARKER, MAX_DATAREF_COUNT, MAX_DATAREF_NAME_LEN,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Size of the null-padded ICAO field in version 2 headers
+const ICAO_FIELD_LEN: usize = 8;
+
+/// Size of the null-padded airport name field in version 2 headers
+const AIRPORT_NAME_FIELD_LEN: usize = 256;
+
+/// Passes writes through while keeping a CRC32 of everything written
+struct ChecksumWriter<'a, W> {
+    inner: &'a mut W,
+    hasher: crc32fast::Hasher,
+}
+
+impl<W: Write> Write for ChecksumWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Write `text` into a fixed-size field, truncating or padding with zeros
+fn write_padded<W: Write>(writer: &mut W, text: &str, len: usize) -> io::Result<()> {
+    let mut field = vec![0u8; len];
+    let bytes = text.as_bytes();
+    let n = bytes.len().min(len);
+    field[..n].copy_from_slice(&bytes[..n]);
+    writer.write_all(&field)
+}
+
+fn write_airport<W: Write>(writer: &mut W, airport: Option<&AirportInfo>) -> io::Result<()> {
+    let (icao, name, lat, lon) = match airport {
+        Some(a) => (a.icao.as_str(), a.name.as_str(), a.lat, a.lon),
+        None => ("", "", 0.0, 0.0),
+    };
+    write_padded(writer, icao, ICAO_FIELD_LEN)?;
+    writer.write_f32::<LittleEndian>(lat)?;
+    writer.write_f32::<LittleEndian>(lon)?;
+    write_padded(writer, name, AIRPORT_NAME_FIELD_LEN)
+}
+
+/// Write a length-prefixed (u8) string value, truncated to 255 bytes
+fn write_string<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    let bytes = text.as_bytes();
+    let len = bytes.len().min(u8::MAX as usize);
+    writer.write_u8(len as u8)?;
+    writer.write_all(&bytes[..len])
+}
+
+fn type_code(dr: &DatarefDef) -> io::Result<u8> {
+    match dr.data_type.as_str() {
+        "float" => Ok(0),
+        "int" => Ok(1),
+        "string" => Ok(2),
+        "double" => Ok(3),
+        other => Err(invalid_data(format!("Cannot write dataref {} of type {}", dr.name, other))),
+    }
+}
+
+fn write_value<W: Write>(writer: &mut W, dr: &DatarefDef, value: &DataValue) -> io::Result<()> {
+    let expected = dr.array_size as usize;
+    let check_len = |len: usize| {
+        if len == expected {
+            Ok(())
+        } else {
+            Err(invalid_data(format!(
+                "Dataref {} expects {} array values, frame has {}",
+                dr.name, expected, len
+            )))
+        }
+    };
+
+    match (dr.array_size > 0, dr.data_type.as_str(), value) {
+        (false, "float", DataValue::Float(v)) => writer.write_f32::<LittleEndian>(*v),
+        (false, "int", DataValue::Int(v)) => writer.write_i32::<LittleEndian>(*v),
+        (false, "double", DataValue::Double(v)) => writer.write_f64::<LittleEndian>(*v),
+        (false, "string", DataValue::String(v)) => write_string(writer, v),
+        (true, "float", DataValue::FloatArray(arr)) => {
+            check_len(arr.len())?;
+            arr.iter().try_for_each(|v| writer.write_f32::<LittleEndian>(*v))
+        }
+        (true, "int", DataValue::IntArray(arr)) => {
+            check_len(arr.len())?;
+            arr.iter().try_for_each(|v| writer.write_i32::<LittleEndian>(*v))
+        }
+        (true, "double", DataValue::DoubleArray(arr)) => {
+            check_len(arr.len())?;
+            arr.iter().try_for_each(|v| writer.write_f64::<LittleEndian>(*v))
+        }
+        (true, "string", DataValue::StringArray(arr)) => {
+            check_len(arr.len())?;
+            arr.iter().try_for_each(|v| write_string(writer, v))
+        }
+        _ => Err(invalid_data(format!(
+            "Value {:?} does not match dataref {} ({})",
+            value, dr.name, dr.data_type
+        ))),
+    }
+}
+
+impl XDRData {
+    /// Write the recording as an `.xdr` file, including the `ENDR` footer and
+    /// the checksum trailer
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Serialize the recording in the little-endian layout `read` expects.
+    /// The footer is followed by `CSUM` and a CRC32 of the frame section,
+    /// which `read` verifies and older readers ignore.
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        // Stay within the limits `read` enforces so the output can be read back
+        let dataref_count = u16::try_from(self.datarefs.len())
+            .ok()
+            .filter(|&n| n <= MAX_DATAREF_COUNT)
+            .ok_or_else(|| invalid_data(format!("Too many datarefs: {}", self.datarefs.len())))?;
+
+        // Header
+        writer.write_all(b"XFDR")?;
+        writer.write_u16::<LittleEndian>(self.header.version)?;
+        writer.write_u8(self.header.level)?;
+        writer.write_f32::<LittleEndian>(self.header.interval)?;
+        writer.write_u64::<LittleEndian>(self.header.start_timestamp)?;
+        if self.header.version >= 2 {
+            write_airport(writer, self.header.departure_airport.as_ref())?;
+            write_airport(writer, self.header.arrival_airport.as_ref())?;
+        }
+        writer.write_u16::<LittleEndian>(dataref_count)?;
+
+        // Dataref definitions
+        for dr in &self.datarefs {
+            let name = dr.name.as_bytes();
+            let name_len = u16::try_from(name.len())
+                .ok()
+                .filter(|&n| n <= MAX_DATAREF_NAME_LEN)
+                .ok_or_else(|| invalid_data(format!("Dataref name too long: {}", dr.name)))?;
+            writer.write_u16::<LittleEndian>(name_len)?;
+            writer.write_all(name)?;
+            writer.write_u8(type_code(dr)?)?;
+            writer.write_u8(dr.array_size)?;
+        }
+
+        // Data frames
+        let mut frame_writer = ChecksumWriter {
+            inner: writer,
+            hasher: crc32fast::Hasher::new(),
+        };
+        for frame in &self.frames {
+            if frame.values.len() != self.datarefs.len() {
+                return Err(invalid_data(format!(
+                    "Frame at {}s has {} values for {} datarefs",
+                    frame.timestamp,
+                    frame.values.len(),
+                    self.datarefs.len()
+                )));
+            }
+            frame_writer.write_all(b"DATA")?;
+            frame_writer.write_f32::<LittleEndian>(frame.timestamp)?;
+            for (dr, value) in self.datarefs.iter().zip(&frame.values) {
+                write_value(&mut frame_writer, dr, value)?;
+            }
+        }
+        let checksum = frame_writer.hasher.finalize();
+
+        // Footer
+        let end_timestamp = self.header.end_timestamp.unwrap_or_else(|| {
+            let elapsed = self.frames.last().map_or(0.0, |f| f.timestamp.max(0.0).ceil());
+            self.header.start_timestamp + elapsed as u64
+        });
+        writer.write_all(b"ENDR")?;
+        writer.write_u32::<LittleEndian>(self.frames.len() as u32)?;
+        writer.write_u64::<LittleEndian>(end_timestamp)?;
+        writer.write_all(CHECKSUM_MARKER)?;
+        writer.write_u32::<LittleEndian>(checksum)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::DEFAULT_MAX_FILE_SIZE;
+    use crate::xdr::DataFrame;
+    use std::fs;
+
+    #[test]
+    fn test_write_round_trip() {
+        let mut data = XDRData::new();
+        data.header.version = 2;
+        data.header.level = 2;
+        data.header.interval = 0.25;
+        data.header.start_timestamp = 1_700_000_000;
+        data.header.departure_airport = Some(AirportInfo {
+            icao: "KSFO".to_string(),
+            name: "San Francisco Intl".to_string(),
+            lat: 37.619,
+            lon: -122.375,
+            valid: true,
+        });
+        for (name, data_type, array_size) in [
+            ("sim/test/float", "float", 0),
+            ("sim/test/ints", "int", 2),
+            ("sim/test/double", "double", 0),
+            ("sim/test/label", "string", 0),
+            ("sim/test/labels", "string", 2),
+        ] {
+            data.datarefs.push(DatarefDef {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+                array_size,
+            });
+        }
+        for i in 0..3 {
+            data.frames.push(DataFrame {
+                timestamp: i as f32 * 0.25,
+                values: vec![
+                    DataValue::Float(i as f32 * 1.5),
+                    DataValue::IntArray(vec![i, -i]),
+                    DataValue::Double(i as f64 / 3.0),
+                    DataValue::String(format!("frame {}", i)),
+                    DataValue::StringArray(vec!["a".to_string(), String::new()]),
+                ],
+            });
+        }
+
+        let first = std::env::temp_dir().join("xdr_test_write_first.xdr");
+        let second = std::env::temp_dir().join("xdr_test_write_second.xdr");
+        data.write(&first).unwrap();
+
+        let read_back = XDRData::read(&first, DEFAULT_MAX_FILE_SIZE).unwrap();
+        assert!(read_back.is_complete());
+        assert_eq!(read_back.integrity_ok(), Some(true));
+        assert_eq!(read_back.header.total_records, Some(3));
+        assert_eq!(read_back.header.departure_airport.as_ref().unwrap().icao, "KSFO");
+        assert!(read_back.header.arrival_airport.is_none());
+        assert_eq!(read_back.frames.len(), 3);
+
+        read_back.write(&second).unwrap();
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+    }
+
+    #[test]
+    fn test_checksum_detects_corruption() {
+        let mut data = XDRData::new();
+        data.header.version = 1;
+        data.datarefs.push(DatarefDef {
+            name: "sim/test/value".to_string(),
+            data_type: "float".to_string(),
+            array_size: 0,
+        });
+        for i in 0..3 {
+            data.frames.push(DataFrame {
+                timestamp: i as f32,
+                values: vec![DataValue::Float(i as f32 * 10.0)],
+            });
+        }
+
+        let mut bytes = Vec::new();
+        data.write_to(&mut bytes).unwrap();
+        let read = |bytes: &[u8]| XDRData::from_reader(io::Cursor::new(bytes.to_vec()), "test").unwrap();
+        assert_eq!(read(&bytes).integrity_ok(), Some(true));
+
+        // Flip a bit in the last frame's value (just before the 16-byte
+        // footer and 8-byte trailer)
+        let mut corrupted = bytes.clone();
+        let value_at = corrupted.len() - 24 - 1;
+        corrupted[value_at] ^= 0x01;
+        let damaged = read(&corrupted);
+        assert!(damaged.is_complete());
+        assert_eq!(damaged.integrity_ok(), Some(false));
+
+        // Files written without the trailer still load, unverified
+        let legacy = read(&bytes[..bytes.len() - 8]);
+        assert!(legacy.is_complete());
+        assert_eq!(legacy.frames.len(), 3);
+        assert_eq!(legacy.integrity_ok(), None);
+    }
+
+    #[test]
+    fn test_write_rejects_mismatched_values() {
+        let mut data = XDRData::new();
+        data.header.version = 1;
+        data.datarefs.push(DatarefDef {
+            name: "sim/test/value".to_string(),
+            data_type: "float".to_string(),
+            array_size: 0,
+        });
+        data.frames.push(DataFrame {
+            timestamp: 0.0,
+            values: vec![DataValue::Int(1)],
+        });
+
+        let mut bytes = Vec::new();
+        let err = data.write_to(&mut bytes).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
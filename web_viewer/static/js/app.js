@@ -51,11 +51,11 @@ const api = window.xdrApi || {
         return response.json();
     },
 
-    async getStatistics(params) {
+    async getStatistics(params, timeRange = null) {
         const response = await fetch('/api/statistics', {
             method: 'POST',
             headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ parameters: params })
+            body: JSON.stringify({ parameters: params, time_range: timeRange })
         });
         return response.json();
     },
@@ -344,12 +344,21 @@ const ui = {
 };
 
 // Parameter List Functions
+// Friendly label for a raw parameter name, falling back to the name itself
+function paramLabel(name) {
+    const param = state.parameters.find(p => p.name === name);
+    return (param && param.display_name) || name;
+}
+
+function matchesFilter(param, filterLower) {
+    return param.name.toLowerCase().includes(filterLower) ||
+        (param.display_name || '').toLowerCase().includes(filterLower);
+}
+
 function renderParameterList(filter = '') {
     const listEl = document.getElementById('param-list');
     const filterLower = filter.toLowerCase();
-    const filteredParams = state.parameters.filter(p => 
-        p.name.toLowerCase().includes(filterLower)
-    );
+    const filteredParams = state.parameters.filter(p => matchesFilter(p, filterLower));
 
     if (filteredParams.length === 0) {
         listEl.innerHTML = `<div class="param-placeholder">
@@ -365,7 +374,7 @@ function renderParameterList(filter = '') {
         return `
             <div class="param-item ${isSelected ? 'selected' : ''}" data-index="${state.parameters.indexOf(param)}" style="animation-delay: ${animDelay}ms;">
                 <input type="checkbox" ${isSelected ? 'checked' : ''}>
-                <span class="param-name" title="${param.name}">${param.name}</span>
+                <span class="param-name" title="${param.name}">${param.display_name || param.name}</span>
                 <div class="param-color" style="background-color: ${state.colors[colorIdx]}"></div>
             </div>
         `;
@@ -410,9 +419,7 @@ function toggleParam(idx) {
 
 function selectAllParams() {
     const filter = document.getElementById('param-search').value.toLowerCase();
-    state.selectedParams = state.parameters.filter(p => 
-        p.name.toLowerCase().includes(filter)
-    );
+    state.selectedParams = state.parameters.filter(p => matchesFilter(p, filter));
     renderParameterList(filter);
     ui.updateSelectionCount();
 }
@@ -490,6 +497,14 @@ async function loadFlightAnalysis() {
                 <span class="stat-value" style="${gColor}">${result.landing_g_force.toFixed(2)}G</span>
             </div>`;
         }
+
+        if (result.landing_quality) {
+            const qualityColor = result.landing_quality === 'hard' ? 'color: #ff6b6b;' : '';
+            html += `<div class="stat-item">
+                <span class="stat-label">Landing</span>
+                <span class="stat-value" style="${qualityColor}">${result.landing_quality}</span>
+            </div>`;
+        }
         
         html += '</div></div>';
 
@@ -548,13 +563,16 @@ async function loadFlightAnalysis() {
             result.anomalies.forEach(anomaly => {
                 const severityClass = `severity-${anomaly.severity}`;
                 const timeMin = (anomaly.timestamp / 60).toFixed(1);
+                const duration = anomaly.end_timestamp > anomaly.timestamp
+                    ? ` (${(anomaly.end_timestamp - anomaly.timestamp).toFixed(1)} s)`
+                    : '';
                 html += `<div class="anomaly-item ${severityClass}">
                     <div class="anomaly-header">
                         <span class="anomaly-severity">${anomaly.severity.toUpperCase()}</span>
-                        <span class="anomaly-time">${timeMin} min</span>
+                        <span class="anomaly-time">${timeMin} min${duration}</span>
                     </div>
                     <div class="anomaly-desc">${anomaly.description}</div>
-                    <div class="anomaly-detail">${anomaly.parameter}: ${anomaly.value.toFixed(1)}</div>
+                    <div class="anomaly-detail">${anomaly.parameter}: ${anomaly.peak_value.toFixed(1)}</div>
                 </div>`;
             });
             
@@ -631,7 +649,7 @@ async function updatePlot() {
             traces.push({
                 x: paramData.timestamps,
                 y: paramData.values,
-                name: name,
+                name: paramLabel(name),
                 type: frameCount > PLOT_PERF_THRESHOLDS.LARGE_DATASET ? 'scattergl' : 'scatter', // Use WebGL for large datasets
                 mode: 'lines',
                 line: { 
@@ -737,7 +755,11 @@ async function loadStatistics() {
     ui.showLoading('Calculating statistics...');
 
     try {
-        const result = await api.getStatistics(state.selectedParams);
+        // Statistics follow the time range selected with the sliders
+        const timeRange = (state.timeRange.start !== 0 || state.timeRange.end !== state.timeRange.max)
+            ? [state.timeRange.start, state.timeRange.end]
+            : null;
+        const result = await api.getStatistics(state.selectedParams, timeRange);
         
         if (result.error) {
             throw new Error(result.error);
@@ -745,7 +767,7 @@ async function loadStatistics() {
 
         container.innerHTML = result.map(stats => `
             <tr>
-                <td>${stats.name}</td>
+                <td>${paramLabel(stats.name)}</td>
                 <td>${stats.count?.toLocaleString() || 'N/A'}</td>
                 <td>${stats.min?.toFixed(4) ?? 'N/A'}</td>
                 <td>${stats.max?.toFixed(4) ?? 'N/A'}</td>
@@ -1027,7 +1049,7 @@ async function loadDataTable() {
         // Build body
         container.innerHTML = result.rows.map(row => {
             let cells = `<td>${row.index}</td><td>${row.timestamp.toFixed(3)}</td>`;
-            cells += row.values.map(v => `<td>${typeof v === 'number' ? v.toFixed(4) : v}</td>`).join('');
+            cells += row.values.map(v => `<td>${v === null ? '—' : typeof v === 'number' ? v.toFixed(4) : v}</td>`).join('');
             return `<tr>${cells}</tr>`;
         }).join('');
 
@@ -1165,6 +1187,14 @@ async function handleFileUpload(file) {
             ui.showToast('Warning: File contains no data frames', 'warning');
         }
 
+        if (result.truncated) {
+            ui.showToast('Warning: Recording is incomplete (no end marker) and may be truncated', 'warning');
+        }
+
+        if (result.schema_warnings && result.schema_warnings.length > 0) {
+            ui.showToast(`Warning: ${result.schema_warnings.length} dataref name(s) are corrupted and may display incorrectly`, 'warning');
+        }
+
         // Store data
         state.header = result.header;
         state.parameters = result.parameters || [];
@@ -1209,7 +1239,8 @@ async function handlePathLoad(path) {
         return;
     }
     
-    if (!path.toLowerCase().endsWith('.xdr')) {
+    const lowerPath = path.toLowerCase();
+    if (!lowerPath.endsWith('.xdr') && !lowerPath.endsWith('.xdr.gz')) {
         ui.showError(ErrorTypes.FILE_TYPE, `Path "${path}" does not point to an XDR file`);
         return;
     }
@@ -1217,6 +1248,14 @@ async function handlePathLoad(path) {
     ui.showLoading('Loading file...');
     ui.updateStatus('Loading file...', 'loading');
 
+    // The desktop app reports parse progress for large recordings
+    const stopProgress = window.__TAURI__?.event
+        ? await window.__TAURI__.event.listen('load-progress', ({ payload }) => {
+            const percent = Math.floor((payload.bytes_read / Math.max(payload.total_bytes, 1)) * 100);
+            ui.showLoading(`Loading file... ${percent}%`);
+        })
+        : null;
+
     try {
         const result = await api.loadFile(path);
         
@@ -1234,6 +1273,14 @@ async function handlePathLoad(path) {
             ui.showToast('Warning: File contains no data frames', 'warning');
         }
 
+        if (result.truncated) {
+            ui.showToast('Warning: Recording is incomplete (no end marker) and may be truncated', 'warning');
+        }
+
+        if (result.schema_warnings && result.schema_warnings.length > 0) {
+            ui.showToast(`Warning: ${result.schema_warnings.length} dataref name(s) are corrupted and may display incorrectly`, 'warning');
+        }
+
         // Store data
         state.header = result.header;
         state.parameters = result.parameters || [];
@@ -1271,6 +1318,7 @@ async function handlePathLoad(path) {
             ui.showError(ErrorTypes.UNKNOWN, error.message);
         }
     } finally {
+        if (stopProgress) stopProgress();
         ui.hideLoading();
     }
 }
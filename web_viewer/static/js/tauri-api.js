@@ -39,7 +39,7 @@ const tauriApi = isTauri ? {
             multiple: false,
             filters: [{
                 name: 'XDR Files',
-                extensions: ['xdr']
+                extensions: ['xdr', 'gz']
             }]
         });
 
@@ -205,18 +205,19 @@ const api = {
         }
     },
 
-    async getStatistics(params) {
+    async getStatistics(params, timeRange = null) {
         if (isTauri) {
             return await tauriApi.invoke('get_statistics', {
                 request: {
-                    parameters: params
+                    parameters: params,
+                    time_range: timeRange
                 }
             });
         } else {
             const response = await fetch('/api/statistics', {
                 method: 'POST',
                 headers: { 'Content-Type': 'application/json' },
-                body: JSON.stringify({ parameters: params })
+                body: JSON.stringify({ parameters: params, time_range: timeRange })
             });
             return response.json();
         }